@@ -0,0 +1,57 @@
+//! Times `examples/fib.dr` with a larger `n`, both on its own and after thousands of globals have
+//! been defined, since calls should not slow down as more gets defined.
+//!
+//! Run with `cargo bench --bench fib`.
+
+use std::{
+    path::PathBuf,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+const N: u32 = 25;
+const GLOBALS: usize = 10_000;
+const RUNS: usize = 5;
+
+/// The fastest of [`RUNS`] runs of `source`.
+fn time(name: &str, source: &str) -> Duration {
+    let path = std::env::temp_dir().join(format!("draca-bench-{name}.dr"));
+    std::fs::write(&path, source).expect("could not write the benchmark script");
+
+    let fastest = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let status = Command::new(env!("CARGO_BIN_EXE_draca"))
+                .arg(&path)
+                .output()
+                .expect("could not run draca")
+                .status;
+            assert!(status.success(), "`{name}` failed");
+            start.elapsed()
+        })
+        .min()
+        .expect("ran at least once");
+
+    let _ = std::fs::remove_file(path);
+    fastest
+}
+
+fn main() {
+    let example = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples/fib.dr");
+    let example = std::fs::read_to_string(example).expect("could not read examples/fib.dr");
+    assert!(
+        example.contains("(fib 4)"),
+        "examples/fib.dr no longer calls `(fib 4)`"
+    );
+    let fib = example.replace("(fib 4)", &format!("(fib {N})"));
+
+    let globals = (0..GLOBALS)
+        .map(|i| format!("(define global-{i} {i})\n"))
+        .collect::<String>();
+
+    println!("fib {N}: {:?}", time("fib", &fib));
+    println!(
+        "fib {N} after {GLOBALS} globals: {:?}",
+        time("fib-globals", &(globals + &fib))
+    );
+}
@@ -0,0 +1,103 @@
+//! The stack of Draca procedure calls currently being evaluated.
+//!
+//! Tail calls replace the frame they are made from, the same way an optimized Rust backtrace skips
+//! inlined functions.
+
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::source::Span;
+
+/// One procedure call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    /// The name the procedure was defined under, or `<lambda>`.
+    pub name: Rc<str>,
+    /// How many arguments it was called with.
+    pub args: usize,
+    /// Where it was called from.
+    pub span: Option<Span>,
+}
+
+/// A snapshot of the call stack, innermost call first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Backtrace {
+    frames: Vec<CallFrame>,
+}
+
+thread_local! {
+    static STACK: RefCell<Vec<CallFrame>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn push(frame: CallFrame) {
+    STACK.with_borrow_mut(|stack| stack.push(frame));
+}
+
+pub fn pop() {
+    STACK.with_borrow_mut(|stack| stack.pop());
+}
+
+/// Replace the innermost frame, for a tail call.
+pub fn replace(frame: CallFrame) {
+    STACK.with_borrow_mut(|stack| match stack.last_mut() {
+        Some(top) => *top = frame,
+        None => stack.push(frame),
+    });
+}
+
+pub fn depth() -> usize {
+    STACK.with_borrow(Vec::len)
+}
+
+/// The current call stack.
+pub fn current() -> Backtrace {
+    STACK.with_borrow(|stack| Backtrace {
+        frames: stack.iter().rev().cloned().collect(),
+    })
+}
+
+/// Unwind the stack down to `depth`, handing back the frames that were removed.
+pub fn unwind_to(depth: usize) -> Backtrace {
+    STACK.with_borrow_mut(|stack| Backtrace {
+        frames: stack.drain(depth.min(stack.len())..).rev().collect(),
+    })
+}
+
+impl Backtrace {
+    pub fn frames(&self) -> &[CallFrame] {
+        &self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Add the frames of `outer`, which were called before any of ours.
+    pub fn extend(&mut self, outer: Backtrace) {
+        self.frames.extend(outer.frames);
+    }
+}
+
+impl Display for Backtrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stack backtrace:")?;
+
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let plural = if frame.args == 1 { "" } else { "s" };
+            write!(
+                f,
+                "\n{idx:>4}: {} ({} argument{plural})",
+                frame.name, frame.args
+            )?;
+
+            if let Some(location) = frame.span.and_then(|span| span.location()) {
+                write!(
+                    f,
+                    "\n             at {}:{}:{}",
+                    location.name, location.line, location.column
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
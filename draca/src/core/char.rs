@@ -0,0 +1,168 @@
+use crate::{
+    error::{ArgCount, DracaError},
+    int,
+    number::Number,
+    parser::Expression,
+};
+
+fn char_arg(args: &[Expression], name: &str) -> Result<char, DracaError> {
+    match args {
+        [Expression::Char(c)] => Ok(*c),
+        [other] => Err(DracaError::type_mismatch("char", other.type_name())),
+        _ => Err(DracaError::arity(name, ArgCount::Exactly(1), args.len())),
+    }
+}
+
+macro_rules! predicates {
+    ( $( ($name:ident, $method:ident, $draca:expr) ),* $(,)? ) => {
+        $(
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
+                Ok(Expression::Bool(char_arg(args, $draca)?.$method()))
+            }
+        )*
+    };
+}
+
+predicates![
+    (is_alphabetic, is_alphabetic, "alphabetic?"),
+    (is_numeric, is_numeric, "numeric?"),
+    (is_alphanumeric, is_alphanumeric, "alphanumeric?"),
+    (is_whitespace, is_whitespace, "whitespace?"),
+    (is_upper, is_uppercase, "upper?"),
+    (is_lower, is_lowercase, "lower?"),
+];
+
+/// Change the case of a character, leaving it alone if the other case takes more than one
+/// character to write, as with `ß`.
+macro_rules! case_changes {
+    ( $( ($name:ident, $method:ident, $draca:expr) ),* $(,)? ) => {
+        $(
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
+                let c = char_arg(args, $draca)?;
+                let mut changed = c.$method();
+                Ok(Expression::Char(match (changed.next(), changed.next()) {
+                    (Some(changed), None) => changed,
+                    _ => c,
+                }))
+            }
+        )*
+    };
+}
+
+case_changes![
+    (to_upper, to_uppercase, "to-upper"),
+    (to_lower, to_lowercase, "to-lower"),
+];
+
+/// The unicode code point of a character.
+pub fn to_int(args: &[Expression]) -> Result<Expression, DracaError> {
+    Ok(int!(i64::from(u32::from(char_arg(args, "char->int")?))))
+}
+
+/// The character with a code point, or `None` if there is no such character.
+pub fn from_int(args: &[Expression]) -> Result<Expression, DracaError> {
+    match args {
+        [Expression::Number(Number::Int(n))] => Ok(u32::try_from(*n)
+            .ok()
+            .and_then(char::from_u32)
+            .map_or_else(Expression::none, |c| Expression::some(Expression::Char(c)))),
+        [Expression::Number(Number::Big(_))] => Ok(Expression::none()),
+        [other] => Err(DracaError::type_mismatch("int", other.type_name())),
+        _ => Err(DracaError::arity(
+            "int->char",
+            ArgCount::Exactly(1),
+            args.len(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{env::Environment, lisp};
+
+    use super::*;
+
+    fn setup_env() -> Environment {
+        Environment::empty().core().stdlib().build()
+    }
+
+    #[test]
+    fn literals() {
+        let mut env = setup_env();
+
+        assert_eq!(lisp!("(quote #\\a)", &mut env), Expression::Char('a'));
+        assert_eq!(lisp!("(quote #\\space)", &mut env), Expression::Char(' '));
+        assert_eq!(lisp!("(quote #\\()", &mut env), Expression::Char('('));
+        assert_eq!(
+            lisp!("(quote #\\u{1F600})", &mut env),
+            Expression::Char('😀')
+        );
+        assert!(crate::parser::parse("(quote #\\spaces)").is_err());
+        assert!(crate::parser::parse("(quote #\\u{D800})").is_err());
+
+        for c in ['a', ' ', '\n', '(', '😀', '\u{7}'] {
+            let printed = Expression::Char(c).to_string();
+            assert_eq!(
+                lisp!(&format!("(quote {printed})"), &mut env),
+                Expression::Char(c)
+            );
+        }
+        assert_eq!(Expression::Char('\u{7}').to_string(), "#\\u{7}");
+
+        assert_eq!(
+            lisp!("(format \"{0}{1}\" #\\o #\\k)", &mut env),
+            Expression::string("ok")
+        );
+        assert_eq!(
+            lisp!("(format \"{0}\" (list #\\a #\\space))", &mut env),
+            Expression::string("(#\\a #\\space)")
+        );
+    }
+
+    #[test]
+    fn char_module() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(alphabetic? #\\é)", &mut env),
+            Expression::Bool(true)
+        );
+        assert_eq!(lisp!("(numeric? #\\7)", &mut env), Expression::Bool(true));
+        assert_eq!(
+            lisp!("(whitespace? #\\tab)", &mut env),
+            Expression::Bool(true)
+        );
+        assert_eq!(lisp!("(upper? #\\a)", &mut env), Expression::Bool(false));
+        assert_eq!(lisp!("(to-upper #\\a)", &mut env), Expression::Char('A'));
+        assert_eq!(lisp!("(to-upper #\\ß)", &mut env), Expression::Char('ß'));
+        assert_eq!(lisp!("(to-lower #\\Q)", &mut env), Expression::Char('q'));
+        assert_eq!(lisp!("(char->int #\\A)", &mut env), int!(65));
+        assert_eq!(
+            lisp!("(int->char 955)", &mut env),
+            Expression::some(Expression::Char('λ'))
+        );
+        assert_eq!(lisp!("(int->char -1)", &mut env), Expression::none());
+        assert!(matches!(
+            crate::eval::eval_expr(
+                crate::parser::parse("(numeric? \"7\")").unwrap()[0].clone(),
+                &mut env
+            )
+            .map_err(DracaError::into_kind),
+            Err(DracaError::TypeMismatch { expected, found }) if expected == "char" && found == "string"
+        ));
+
+        assert_eq!(
+            lisp!("(string->list \"ab\")", &mut env),
+            Expression::list([Expression::Char('a'), Expression::Char('b')])
+        );
+        assert_eq!(
+            lisp!(
+                "(list->string (cons (to-upper #\\s) (cdr (string->list \"shout\"))))",
+                &mut env
+            ),
+            Expression::string("Shout")
+        );
+        assert_eq!(lisp!("(= #\\a #\\a)", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(< #\\a #\\b)", &mut env), Expression::Bool(true));
+    }
+}
@@ -1,8 +1,11 @@
 use std::ops::Not;
 
-use crate::parser::Expression;
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::Expression,
+};
 
-type Ret = Result<Expression, String>;
+type Ret = Result<Expression, DracaError>;
 
 macro_rules! impl_cmp_ops {
     ($(($name:tt, $draca:expr)),* $(,)?) => {
@@ -10,7 +13,7 @@ macro_rules! impl_cmp_ops {
             pub fn $name(args: &[Expression]) -> Ret {
                 match args {
                     [first, second] => Ok(Expression::Bool(first.$name(second))),
-                    _ => Err(format!("`{}` requires two arguments", stringify!($draca))),
+                    _ => Err(DracaError::arity($draca, ArgCount::Exactly(2), args.len())),
                 }
             }
         )*
@@ -31,8 +34,8 @@ pub fn not(args: &[Expression]) -> Ret {
         [first] | [first, ..] => match first {
             Expression::Nil => Ok(Expression::Nil),
             Expression::Bool(b) => Ok(Expression::Bool(b.not())),
-            _ => Err("`not` expects either a `nil` or a `bool`".into()),
+            other => Err(DracaError::type_mismatch("bool or nil", other.type_name())),
         },
-        _ => Err("`not` requires a single argument".into()),
+        _ => Err(DracaError::arity("not", ArgCount::Exactly(1), args.len())),
     }
 }
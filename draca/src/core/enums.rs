@@ -0,0 +1,87 @@
+//! The functions behind the constructors, predicates and method type checks that `defenum!`
+//! defines. Like [`super::structs`], each takes a value of the enum as a prototype, so it knows
+//! which enum it is working on.
+
+use std::rc::Rc;
+
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::{Enum, Expression},
+};
+
+fn prototype<'a>(args: &'a [Expression], name: &str) -> Result<&'a Rc<Enum>, DracaError> {
+    match args.first() {
+        Some(Expression::Enum(proto)) => Ok(proto),
+        Some(other) => Err(DracaError::type_mismatch("enum", other.type_name())),
+        None => Err(DracaError::arity(name, ArgCount::AtLeast(1), 0)),
+    }
+}
+
+fn is_instance(proto: &Enum, value: &Expression) -> bool {
+    matches!(value, Expression::Enum(en) if Rc::ptr_eq(&en.ty, &proto.ty))
+}
+
+/// `(make proto variant values...)`
+pub fn make(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "make")?;
+
+    let Some(Expression::Symbol(name)) = args.get(1) else {
+        return Err(DracaError::syntax("expected a variant name"));
+    };
+
+    let Some(variant) = proto.ty.variant(name) else {
+        return Err(DracaError::other(format!(
+            "enum `{}` has no variant `{name}`",
+            proto.ty.name
+        )));
+    };
+
+    let values = &args[2..];
+    let arity = proto.ty.variants[variant].1;
+
+    if values.len() != arity {
+        return Err(DracaError::arity(
+            name.as_str(),
+            ArgCount::Exactly(arity),
+            values.len(),
+        ));
+    }
+
+    Ok(Expression::Enum(Rc::new(Enum {
+        ty: Rc::clone(&proto.ty),
+        variant,
+        values: values.to_vec(),
+    })))
+}
+
+/// `(is? proto value)`
+pub fn is(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "is?")?;
+
+    match &args[1..] {
+        [value] => Ok(Expression::Bool(is_instance(proto, value))),
+        rest => Err(DracaError::arity(
+            "is?",
+            ArgCount::Exactly(2),
+            rest.len() + 1,
+        )),
+    }
+}
+
+/// `(expect proto value)`: raise an error unless `value` is of the same enum as `proto`.
+pub fn expect(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "expect")?;
+
+    match &args[1..] {
+        [value] if is_instance(proto, value) => Ok(value.clone()),
+        [value] => Err(DracaError::type_mismatch(
+            format!("enum `{}`", proto.ty.name),
+            value.type_name(),
+        )),
+        rest => Err(DracaError::arity(
+            "expect",
+            ArgCount::Exactly(2),
+            rest.len() + 1,
+        )),
+    }
+}
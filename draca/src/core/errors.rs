@@ -0,0 +1,35 @@
+//! Accessors for the `error` structs that `catch` binds.
+
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::Expression,
+};
+
+macro_rules! accessors {
+    ( $( ($name:ident, $idx:expr, $draca:expr) ),* $(,)? ) => {
+        $(
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
+                match args {
+                    [Expression::Struct(st)] if st.is_error() => Ok(st.values[$idx].clone()),
+                    [other] => Err(DracaError::type_mismatch("error", other.type_name())),
+                    _ => Err(DracaError::arity($draca, ArgCount::Exactly(1), args.len())),
+                }
+            }
+        )*
+    };
+}
+
+accessors![(kind, 0, "error->kind"), (message, 1, "error->message"),];
+
+pub fn is_error(args: &[Expression]) -> Result<Expression, DracaError> {
+    match args {
+        [value] => Ok(Expression::Bool(
+            matches!(value, Expression::Struct(st) if st.is_error()),
+        )),
+        _ => Err(DracaError::arity(
+            "error?",
+            ArgCount::Exactly(1),
+            args.len(),
+        )),
+    }
+}
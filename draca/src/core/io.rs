@@ -0,0 +1,28 @@
+use std::io::BufRead;
+
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::Expression,
+};
+
+/// The next line of standard input without its line ending, or `None` at the end of input.
+pub fn read_line(args: &[Expression]) -> Result<Expression, DracaError> {
+    if !args.is_empty() {
+        return Err(DracaError::arity(
+            "read-line",
+            ArgCount::Exactly(0),
+            args.len(),
+        ));
+    }
+
+    let mut line = String::new();
+
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(Expression::none());
+    }
+
+    let len = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(len);
+
+    Ok(Expression::some(Expression::String(line)))
+}
@@ -1,6 +1,12 @@
-use crate::{empty_quoted_list, num, parser::Expression};
-
-pub fn car(args: &[Expression]) -> Result<Expression, String> {
+use crate::{
+    empty_quoted_list,
+    error::{ArgCount, DracaError},
+    int,
+    number::Number,
+    parser::Expression,
+};
+
+pub fn car(args: &[Expression]) -> Result<Expression, DracaError> {
     match args {
         [first, ..] => match first {
             Expression::List(lst) => Ok(lst.first().cloned().unwrap_or(Expression::Nil)),
@@ -10,17 +16,17 @@ pub fn car(args: &[Expression]) -> Result<Expression, String> {
             },
             _ => Ok(empty_quoted_list!()),
         },
-        _ => Err("`car` requires one argument".into()),
+        [] => Err(DracaError::arity("car", ArgCount::Exactly(1), 0)),
     }
 }
 
-pub fn cdr(args: &[Expression]) -> Result<Expression, String> {
+pub fn cdr(args: &[Expression]) -> Result<Expression, DracaError> {
     match args {
         [Expression::List(lst), ..] => {
             if lst.is_empty() {
                 Ok(Expression::Nil)
             } else {
-                Ok(Expression::List(lst[1..].to_vec()))
+                Ok(Expression::list(lst[1..].to_vec()))
             }
         }
         [Expression::Quoted(quote), ..] => match &**quote {
@@ -28,44 +34,49 @@ pub fn cdr(args: &[Expression]) -> Result<Expression, String> {
                 if lst.is_empty() {
                     Ok(Expression::Nil)
                 } else {
-                    Ok(Expression::List(lst[1..].to_vec()))
+                    Ok(Expression::list(lst[1..].to_vec()))
                 }
             }
             _ => Ok(Expression::Nil),
         },
-        _ => Err("`cdr` requires one argument".into()),
+        [other, ..] => Err(DracaError::type_mismatch("list", other.type_name())),
+        [] => Err(DracaError::arity("cdr", ArgCount::Exactly(1), 0)),
     }
 }
 
-pub fn cons(args: &[Expression]) -> Result<Expression, String> {
+pub fn cons(args: &[Expression]) -> Result<Expression, DracaError> {
     if let [head, tail] = args {
         let mut new_lst = extract_list(tail)?;
 
         new_lst.insert(0, head.clone());
-        Ok(Expression::List(new_lst))
+        Ok(Expression::list(new_lst))
     } else {
-        Err("`cons` requires exactly two arguments".into())
+        Err(DracaError::arity("cons", ArgCount::Exactly(2), args.len()))
     }
 }
 
-pub fn append(args: &[Expression]) -> Result<Expression, String> {
+pub fn append(args: &[Expression]) -> Result<Expression, DracaError> {
     if let [head, tail] = args {
         let mut head_lst = extract_list(head)?;
         let tail_lst = extract_list(tail)?;
 
         head_lst.extend(tail_lst);
 
-        Ok(Expression::List(head_lst))
+        Ok(Expression::list(head_lst))
     } else {
-        Err("`append` requires exactly two arguments".into())
+        Err(DracaError::arity(
+            "append",
+            ArgCount::Exactly(2),
+            args.len(),
+        ))
     }
 }
 
-pub fn list(args: &[Expression]) -> Result<Expression, String> {
-    Ok(Expression::List(args.to_vec()))
+pub fn list(args: &[Expression]) -> Result<Expression, DracaError> {
+    Ok(Expression::list(args.to_vec()))
 }
 
-pub fn is_empty(args: &[Expression]) -> Result<Expression, String> {
+pub fn is_empty(args: &[Expression]) -> Result<Expression, DracaError> {
     Ok(match args {
         [Expression::List(lst)] | [Expression::Quoted(box Expression::List(lst))] => {
             Expression::Bool(lst.is_empty())
@@ -76,56 +87,107 @@ pub fn is_empty(args: &[Expression]) -> Result<Expression, String> {
     })
 }
 
-pub fn len(args: &[Expression]) -> Result<Expression, String> {
+pub fn len(args: &[Expression]) -> Result<Expression, DracaError> {
     match args {
-        [first] => Ok(num!(extract_list(first)?.len() as f64)),
+        [first] => Ok(int!(extract_list(first)?.len() as i64)),
         // NOTE: Special Draca case where if there are multiple arguments, `len` will give the
         // count of the arguments.
-        [_, rest @ ..] => Ok(num!(rest.len() as f64 + 1.0)),
-        _ => Ok(num!(0.0)),
+        [_, rest @ ..] => Ok(int!(rest.len() as i64 + 1)),
+        _ => Ok(int!(0)),
+    }
+}
+
+/// `(range end)`, `(range start end)` or `(range start end step)`: the numbers from `start`
+/// (default 0) up to but not including `end`, which are integers unless any argument is a float.
+pub fn range(args: &[Expression]) -> Result<Expression, DracaError> {
+    let mut numbers = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            Expression::Number(n) => numbers.push(n.clone()),
+            other => {
+                return Err(DracaError::type_mismatch("number", other.type_name()));
+            }
+        }
+    }
+
+    let (start, end, step) = match &numbers[..] {
+        [end] => (&Number::Int(0), end, &Number::Int(1)),
+        [start, end] => (start, end, &Number::Int(1)),
+        [start, end, step] => (start, end, step),
+        _ => {
+            return Err(DracaError::arity(
+                "range",
+                ArgCount::Between(1, 3),
+                args.len(),
+            ));
+        }
+    };
+
+    let zero = &Number::Int(0);
+    if step == zero {
+        return Err(DracaError::other("`range` cannot have a step of 0"));
+    }
+
+    let mut range = Vec::new();
+    let mut n = start.clone();
+    while (step > zero && n < *end) || (step < zero && n > *end) {
+        let next = n.add(step)?;
+        range.push(Expression::Number(std::mem::replace(&mut n, next)));
     }
+
+    Ok(Expression::list(range))
 }
 
-pub(crate) fn extract_list(expr: &Expression) -> Result<Vec<Expression>, String> {
+pub(crate) fn extract_list(expr: &Expression) -> Result<Vec<Expression>, DracaError> {
     match expr {
-        Expression::List(lst) => Ok(lst.clone()),
+        Expression::List(lst) => Ok(lst.to_vec()),
         Expression::Quoted(boxed) => match &**boxed {
-            Expression::List(lst) => Ok(lst.clone()),
-            _ => Err("expected a list".into()),
+            Expression::List(lst) => Ok(lst.to_vec()),
+            other => Err(DracaError::type_mismatch("list", other.type_name())),
         },
-        _ => Err("expected a list".into()),
+        other => Err(DracaError::type_mismatch("list", other.type_name())),
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::sync::{Mutex, OnceLock};
-
     use crate::{env::Environment, lisp};
 
     use super::*;
 
-    static ENV: OnceLock<Mutex<Environment>> = OnceLock::new();
-
-    fn setup_env() -> &'static Mutex<Environment> {
-        ENV.get_or_init(|| Mutex::new(Environment::empty().core().stdlib().build()))
+    fn setup_env() -> Environment {
+        Environment::empty().core().stdlib().build()
     }
 
     #[test]
     fn car() {
-        let env = setup_env();
-        let mut env = env.lock().unwrap();
+        let mut env = setup_env();
         let list = lisp!("(list 1 2 3)", &mut env);
 
-        assert_eq!(super::car(&[list]).unwrap(), num!(1.0));
+        assert_eq!(super::car(&[list]).unwrap(), int!(1));
     }
 
     #[test]
     fn cdr() {
-        let env = setup_env();
-        let mut env = env.lock().unwrap();
+        let mut env = setup_env();
         let list = lisp!("(list 1 2 3)", &mut env);
 
         assert_eq!(super::cdr(&[list]).unwrap(), lisp!("(list 2 3)", &mut env));
     }
+
+    #[test]
+    fn rev() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(rev (list 1 2 3))", &mut env),
+            lisp!("(list 3 2 1)", &mut env)
+        );
+        // Lists are copied by `cons`, `cdr` and every lookup, so each step of `rev` is linear in
+        // the length. This is as deep as stays quick; `fact` and named `let` go a million deep.
+        assert_eq!(lisp!("(len (rev (range 5000)))", &mut env), int!(5000));
+        // The accumulating loops of `rev` and `fact` are local to them.
+        assert_eq!(env.get("rev-onto"), None);
+        assert_eq!(env.get("fact-acc"), None);
+    }
 }
@@ -1,21 +1,27 @@
-use std::collections::HashMap;
+use std::{cell::Cell, collections::HashMap};
 
 use strfmt::strfmt;
 
-use crate::parser::Expression;
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::Expression,
+};
 
-pub fn panic(args: &[Expression]) -> Result<Expression, String> {
+pub fn panic(args: &[Expression]) -> Result<Expression, DracaError> {
     let out = format(args)?;
 
-    panic!("{}", out.fmt_string());
+    Err(DracaError::UserPanic(out.fmt_string()))
 }
 
-pub fn format(args: &[Expression]) -> Result<Expression, String> {
+pub fn format(args: &[Expression]) -> Result<Expression, DracaError> {
     match &args {
         [single] => Ok(Expression::String(single.fmt_string())),
         [fmt_string, rest @ ..] => {
             let Expression::String(fmt_string) = fmt_string else {
-                return Err("format string must begin with string".into());
+                return Err(DracaError::type_mismatch(
+                    "format string",
+                    fmt_string.type_name(),
+                ));
             };
 
             let fmted_args = rest.iter().map(Expression::fmt_string);
@@ -26,18 +32,45 @@ pub fn format(args: &[Expression]) -> Result<Expression, String> {
                 vars.insert(idx, arg);
             }
 
-            let ret = strfmt(fmt_string, &vars).map_err(|e| e.to_string())?;
+            let ret = strfmt(fmt_string, &vars).map_err(|e| DracaError::other(e.to_string()))?;
 
             Ok(Expression::String(ret))
         }
-        [] => Err("format requires at least one argument".into()),
+        [] => Err(DracaError::arity("format", ArgCount::AtLeast(1), 0)),
     }
 }
 
-pub fn println(args: &[Expression]) -> Result<Expression, String> {
+pub fn println(args: &[Expression]) -> Result<Expression, DracaError> {
     let out = format(args)?;
 
     println!("{}", out.fmt_string());
 
     Ok(Expression::Bool(true))
 }
+
+thread_local! {
+    static GENSYM_COUNTER: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A fresh symbol that no other `gensym` call will hand out, for macros to bind without capturing
+/// the caller's names. An optional string argument is used as its prefix.
+pub fn gensym(args: &[Expression]) -> Result<Expression, DracaError> {
+    let prefix = match args {
+        [] => "g",
+        [Expression::String(prefix)] => prefix,
+        [other] => {
+            return Err(DracaError::type_mismatch("string", other.type_name()));
+        }
+        _ => {
+            return Err(DracaError::arity(
+                "gensym",
+                ArgCount::Between(0, 1),
+                args.len(),
+            ));
+        }
+    };
+
+    let id = GENSYM_COUNTER.replace(GENSYM_COUNTER.get() + 1);
+
+    Ok(Expression::symbol(format!("{prefix}__{id}")))
+}
@@ -1,110 +1,518 @@
-use crate::parser::Expression;
+use crate::{
+    error::{ArgCount, DracaError},
+    int,
+    number::{self, Number},
+    parser::Expression,
+};
 
-macro_rules! binary_op {
-    ($name:ident, $op:tt) => {
-        pub fn $name(args: &[Expression]) -> Result<Expression, String> {
-            match args {
-                [Expression::Number(first), Expression::Number(second)] => {
-                    Ok(Expression::Number(first $op second))
-                }
-                [_, _] => Err("invalid arguments, not numbers".into()),
-                _ => Err(format!("`{}` requires at least two arguments", stringify!($name))),
-            }
-        }
+fn not_a_number(found: &Expression) -> DracaError {
+    DracaError::type_mismatch("number", found.type_name())
+}
+
+fn not_an_int(found: &Expression) -> DracaError {
+    DracaError::type_mismatch("int", found.type_name())
+}
+
+fn number(arg: &Expression) -> Result<&Number, DracaError> {
+    match arg {
+        Expression::Number(n) => Ok(n),
+        other => Err(not_a_number(other)),
+    }
+}
+
+/// The two integers the `checked-`, `wrapping-` and `saturating-` functions work on, which must
+/// fit in an `i64`.
+fn ints(args: &[Expression], name: &str) -> Result<(i64, i64), DracaError> {
+    let int = |arg: &Expression| match arg {
+        Expression::Number(Number::Int(n)) => Ok(*n),
+        Expression::Number(Number::Big(n)) => Err(DracaError::Arithmetic(format!(
+            "`{name}` works on 64-bit integers, but `{n}` is too big"
+        ))),
+        other => Err(not_an_int(other)),
     };
+
+    match args {
+        [first, second] => Ok((int(first)?, int(second)?)),
+        _ => Err(DracaError::arity(name, ArgCount::Exactly(2), args.len())),
+    }
 }
 
-macro_rules! method_op {
-    ($name:ident, $op:tt) => {
-        pub fn $name(args: &[Expression]) -> Result<Expression, String> {
+macro_rules! binary_op {
+    ($name:ident, $method:ident, $draca:expr) => {
+        pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
             match args {
-                [Expression::Number(first), Expression::Number(second)] => {
-                    Ok(Expression::Number(first.$op(*second)))
-                }
-                [_, _] => Err("invalid arguments, not numbers".into()),
-                _ => Err(format!(
-                    "`{}` requires at least two arguments",
-                    stringify!($name)
-                )),
+                [first, second] => Ok(Expression::Number(number(first)?.$method(number(second)?)?)),
+                _ => Err(DracaError::arity($draca, ArgCount::Exactly(2), args.len())),
             }
         }
     };
 }
 
 macro_rules! def_ops {
-    ( $( ($name:ident, $op:tt) ),* $(,)? ) => {
+    ( $( ($name:ident, $method:ident, $draca:expr) ),* $(,)? ) => {
         $(
-            pub fn $name(args: &[Expression]) -> Result<Expression, String> {
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
                 match args {
-                    [Expression::Number(start), tail @ ..] => {
-                        let mut base = *start;
+                    [start, tail @ ..] => {
+                        let mut base = number(start)?.clone();
                         for arg in tail {
-                            match arg {
-                                Expression::Number(n) => base $op n,
-                                _ => return Err(String::from("Expected a number")),
-                            }
+                            base = base.$method(number(arg)?)?;
                         }
                         Ok(Expression::Number(base))
                     }
-                    [_, _] => Err(String::from("Expected a number")),
-                    _ => Err(format!("`{}` requires at least two arguments", stringify!($name))),
+                    [] => Err(DracaError::arity($draca, ArgCount::AtLeast(1), 0)),
                 }
             }
         )*
     };
 }
 
-// Sub has a special case with just one argument which inverts it.
-pub fn sub(args: &[Expression]) -> Result<Expression, String> {
-    match args {
-        [single] => {
-            if let Expression::Number(n) = single {
-                Ok(Expression::Number(n.abs()))
-            } else {
-                Err(String::from("Expected a number"))
+/// Integer operations, given the two integers as `$a` and `$b`, with `$wrap` turning the result
+/// into an expression.
+macro_rules! int_ops {
+    ( $wrap:expr; $( ($name:ident, $draca:expr, |$a:ident, $b:ident| $body:expr) ),* $(,)? ) => {
+        $(
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
+                let ($a, $b) = ints(args, $draca)?;
+                Ok($wrap($body))
             }
-        }
+        )*
+    };
+}
+
+// Sub has a special case with just one argument which negates it.
+pub fn sub(args: &[Expression]) -> Result<Expression, DracaError> {
+    match args {
+        [single] => Ok(Expression::Number(number(single)?.neg()?)),
         [start, tail @ ..] => {
-            let mut base = if let Expression::Number(n) = start {
-                *n
-            } else {
-                return Err(String::from("Expected a number"));
-            };
+            let mut base = number(start)?.clone();
             for arg in tail {
-                match arg {
-                    Expression::Number(n) => base -= n,
-                    _ => return Err(String::from("Expected a number")),
-                }
+                base = base.sub(number(arg)?)?;
             }
             Ok(Expression::Number(base))
         }
-        _ => panic!("oops"),
+        [] => Err(DracaError::arity("-", ArgCount::AtLeast(1), 0)),
     }
 }
 
-pub fn ash(args: &[Expression]) -> Result<Expression, String> {
-    match args {
-        [Expression::Number(first), Expression::Number(second)] => {
-            if second.is_sign_negative() {
-                Ok(Expression::Number(
-                    ((first.round() as u64) >> second.round() as u64) as f64,
-                ))
-            } else {
-                Ok(Expression::Number(
-                    ((first.round() as u64) << second.round() as u64) as f64,
-                ))
-            }
+/// Shift an integer left by a number of bits, or right if it is negative, keeping its sign.
+pub fn ash(args: &[Expression]) -> Result<Expression, DracaError> {
+    let (n, shift) = match args {
+        [
+            Expression::Number(n @ (Number::Int(_) | Number::Big(_))),
+            Expression::Number(Number::Int(shift)),
+        ] => (n, *shift),
+        [Expression::Number(Number::Int(_) | Number::Big(_)), other] | [other, _] => {
+            return Err(not_an_int(other));
         }
-        [_, _] => Err("invalid arguments, not numbers".into()),
-        _ => Err("`ash` requires at least two arguments".into()),
+        _ => return Err(DracaError::arity("ash", ArgCount::Exactly(2), args.len())),
+    };
+
+    let power = Number::Int(2).pow(&Number::Int(shift.saturating_abs()))?;
+
+    if shift < 0 {
+        // Rounding down is what makes this an arithmetic shift for negative numbers.
+        n.div_euclid(&power).map(Expression::Number)
+    } else {
+        n.mul(&power).map(Expression::Number)
     }
 }
 
-def_ops![
-    (add, +=),
-    (mul, *=),
-    (div, /=),
+def_ops![(add, add, "+"), (mul, mul, "*"), (div, div, "/")];
+
+binary_op!(rem, rem, "rem");
+binary_op!(pow, pow, "pow");
+binary_op!(div_euclid, div_euclid, "div-euclid");
+binary_op!(rem_euclid, rem_euclid, "rem-euclid");
+
+int_ops![
+    |n: Option<i64>| n.map_or_else(Expression::none, |n| Expression::some(int!(n)));
+    (checked_add, "checked-add", |a, b| a.checked_add(b)),
+    (checked_sub, "checked-sub", |a, b| a.checked_sub(b)),
+    (checked_mul, "checked-mul", |a, b| a.checked_mul(b)),
+    (checked_div, "checked-div", |a, b| a.checked_div(b)),
+    (checked_rem, "checked-rem", |a, b| a.checked_rem(b)),
+    (checked_pow, "checked-pow", |a, b| {
+        u32::try_from(b).ok().and_then(|b| a.checked_pow(b))
+    }),
 ];
 
-binary_op!(rem, %);
-method_op!(pow, powf);
+int_ops![
+    |n: i64| int!(n);
+    (wrapping_add, "wrapping-add", |a, b| a.wrapping_add(b)),
+    (wrapping_sub, "wrapping-sub", |a, b| a.wrapping_sub(b)),
+    (wrapping_mul, "wrapping-mul", |a, b| a.wrapping_mul(b)),
+    (wrapping_div, "wrapping-div", |a, b| {
+        a.wrapping_div(number::nonzero(b, "divide")?)
+    }),
+    (wrapping_rem, "wrapping-rem", |a, b| {
+        a.wrapping_rem(number::nonzero(b, "calculate the remainder")?)
+    }),
+    (wrapping_pow, "wrapping-pow", |a, b| a.wrapping_pow(number::exponent(b)?)),
+    (saturating_add, "saturating-add", |a, b| a.saturating_add(b)),
+    (saturating_sub, "saturating-sub", |a, b| a.saturating_sub(b)),
+    (saturating_mul, "saturating-mul", |a, b| a.saturating_mul(b)),
+    (saturating_div, "saturating-div", |a, b| {
+        a.saturating_div(number::nonzero(b, "divide")?)
+    }),
+    (saturating_pow, "saturating-pow", |a, b| a.saturating_pow(number::exponent(b)?)),
+];
+
+/// Convert a number to an integer, rounding towards zero. Floats saturate like Rust's `as`.
+pub fn to_int(args: &[Expression]) -> Result<Expression, DracaError> {
+    match args {
+        [arg] => Ok(Expression::Number(number(arg)?.trunc())),
+        _ => Err(DracaError::arity(
+            "number->int",
+            ArgCount::Exactly(1),
+            args.len(),
+        )),
+    }
+}
+
+/// Convert a number to the nearest float.
+pub fn to_inexact(args: &[Expression]) -> Result<Expression, DracaError> {
+    match args {
+        [arg] => Ok(Expression::Number(Number::Float(number(arg)?.as_f64()))),
+        _ => Err(DracaError::arity(
+            "exact->inexact",
+            ArgCount::Exactly(1),
+            args.len(),
+        )),
+    }
+}
+
+macro_rules! fraction_parts {
+    ( $( ($name:ident, $draca:expr) ),* $(,)? ) => {
+        $(
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
+                match args {
+                    [arg] => number(arg)?
+                        .$name()
+                        .map(Expression::Number)
+                        .ok_or_else(|| DracaError::type_mismatch("int or rational", arg.type_name())),
+                    _ => Err(DracaError::arity($draca, ArgCount::Exactly(1), args.len())),
+                }
+            }
+        )*
+    };
+}
+
+fraction_parts![(numerator, "numerator"), (denominator, "denominator")];
+
+#[cfg(test)]
+mod test {
+    use crate::{env::Environment, int, lisp, num};
+
+    use super::*;
+
+    fn setup_env() -> Environment {
+        Environment::empty().core().stdlib().build()
+    }
+
+    /// Evaluate `code` to a number, keeping whether it is an integer or a float.
+    fn number(code: &str, env: &mut Environment) -> Number {
+        match lisp!(code, env) {
+            Expression::Number(n) => n,
+            other => panic!("expected a number, got {other}"),
+        }
+    }
+
+    fn literal(text: &str, env: &mut Environment) -> Number {
+        number(&format!("(quote {text})"), env)
+    }
+
+    fn show(code: &str, env: &mut Environment) -> String {
+        number(code, env).to_string()
+    }
+
+    fn eval_err(code: &str, env: &mut Environment) -> DracaError {
+        let ast = crate::parser::parse(code).expect("Could not parse text!!!");
+        crate::eval::eval_expr(ast[0].clone(), env)
+            .expect_err("Evaluation should have failed")
+            .into_kind()
+    }
+
+    #[test]
+    fn promotion() {
+        let mut env = setup_env();
+
+        assert!(matches!(number("(+ 1 2)", &mut env), Number::Int(3)));
+        assert!(matches!(number("(/ 8 2)", &mut env), Number::Int(4)));
+        assert!(matches!(number("(rem -7 2)", &mut env), Number::Int(-1)));
+        assert!(matches!(number("(- 5)", &mut env), Number::Int(-5)));
+        assert!(matches!(number("(pow 2 10)", &mut env), Number::Int(1024)));
+        assert!(matches!(
+            number("(len (list 1 2))", &mut env),
+            Number::Int(2)
+        ));
+
+        assert!(matches!(number("(+ 1 2.5)", &mut env), Number::Float(3.5)));
+        assert!(matches!(number("(/ 7.0 2)", &mut env), Number::Float(3.5)));
+        assert!(matches!(
+            number("(pow 2.0 -1)", &mut env),
+            Number::Float(0.5)
+        ));
+
+        assert_eq!(lisp!("(= 1 1.0)", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(< 1 1.5)", &mut env), Expression::Bool(true));
+        assert_eq!(
+            lisp!("(format \"{0} {1}\" 1 1.0)", &mut env),
+            Expression::String("1 1.0".into())
+        );
+    }
+
+    #[test]
+    fn integer_division() {
+        let mut env = setup_env();
+
+        assert!(matches!(
+            number("(div-euclid -7 2)", &mut env),
+            Number::Int(-4)
+        ));
+        assert!(matches!(
+            number("(rem-euclid -7 2)", &mut env),
+            Number::Int(1)
+        ));
+        assert!(matches!(
+            eval_err("(/ 1 0)", &mut env),
+            DracaError::Arithmetic(_)
+        ));
+        assert!(matches!(
+            eval_err("(rem 1 0)", &mut env),
+            DracaError::Arithmetic(_)
+        ));
+        assert_eq!(lisp!("(/ 1.0 0)", &mut env), num!(f64::INFINITY));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(checked-add INT-MAX 1)", &mut env),
+            Expression::none()
+        );
+        assert_eq!(
+            lisp!("(checked-mul 2 3)", &mut env),
+            Expression::some(int!(6))
+        );
+        assert_eq!(lisp!("(checked-div 1 0)", &mut env), Expression::none());
+        assert!(matches!(
+            number("(wrapping-add INT-MAX 1)", &mut env),
+            Number::Int(i64::MIN)
+        ));
+        assert!(matches!(
+            number("(saturating-mul INT-MAX 2)", &mut env),
+            Number::Int(i64::MAX)
+        ));
+        assert!(matches!(
+            eval_err("(wrapping-add 1 1.0)", &mut env),
+            DracaError::TypeMismatch { expected, found } if expected == "int" && found == "float"
+        ));
+    }
+
+    #[test]
+    fn ash() {
+        let mut env = setup_env();
+
+        assert!(matches!(number("(ash 1 3)", &mut env), Number::Int(8)));
+        assert!(matches!(number("(ash -8 -1)", &mut env), Number::Int(-4)));
+        assert!(matches!(number("(ash -1 -100)", &mut env), Number::Int(-1)));
+        assert_eq!(
+            lisp!("(format \"{0}\" (ash 1 64))", &mut env),
+            Expression::String("18446744073709551616".into())
+        );
+        assert!(matches!(
+            number("(ash (ash 1 64) -62)", &mut env),
+            Number::Int(4)
+        ));
+    }
+
+    #[test]
+    fn big_integers() {
+        let mut env = setup_env();
+
+        assert_eq!(show("(+ INT-MAX 1)", &mut env), "9223372036854775808");
+        assert_eq!(show("(- INT-MIN)", &mut env), "9223372036854775808");
+        assert_eq!(show("(/ INT-MIN -1)", &mut env), "9223372036854775808");
+        assert_eq!(show("(pow 3 50)", &mut env), "717897987691852588770249");
+        assert_eq!(show("(fact 25)", &mut env), "15511210043330985984000000");
+        assert_eq!(
+            show("(* 123456789012345678901234567890 -987654321)", &mut env),
+            "-121932631124828532112482853211126352690"
+        );
+        assert_eq!(
+            show(
+                "(/ 121932631124828532112482853211126352690 987654321)",
+                &mut env
+            ),
+            "123456789012345678901234567890"
+        );
+        assert_eq!(
+            show("(rem 100000000000000000000007 100000000000)", &mut env),
+            "7"
+        );
+
+        assert!(matches!(
+            number("(- (+ INT-MAX 1) 1)", &mut env),
+            Number::Int(i64::MAX)
+        ));
+        assert_eq!(
+            lisp!("(< INT-MAX 9223372036854775808)", &mut env),
+            Expression::Bool(true)
+        );
+        assert!(matches!(
+            number("(* INT-MAX INT-MAX)", &mut env),
+            Number::Big(_)
+        ));
+    }
+
+    #[test]
+    fn deep_fact() {
+        let mut env = setup_env();
+
+        // A float keeps each step cheap, so this only tests that `fact` loops a million times.
+        assert_eq!(lisp!("(fact 1000000.0)", &mut env), num!(f64::INFINITY));
+    }
+
+    #[test]
+    fn rationals() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!(
+                "(format \"{0} {1} {2}\" (/ 1 3) (/ -6 4) (pow 2 -3))",
+                &mut env
+            ),
+            Expression::String("1/3 -3/2 1/8".into())
+        );
+        assert!(matches!(
+            number("(+ (/ 1 3) (/ 2 3))", &mut env),
+            Number::Int(1)
+        ));
+        assert!(matches!(
+            number("(numerator (/ 6 4))", &mut env),
+            Number::Int(3)
+        ));
+        assert!(matches!(
+            number("(denominator (/ 6 -4))", &mut env),
+            Number::Int(2)
+        ));
+        assert!(matches!(
+            number("(denominator 5)", &mut env),
+            Number::Int(1)
+        ));
+        assert!(matches!(
+            number("(exact->inexact (/ 1 4))", &mut env),
+            Number::Float(0.25)
+        ));
+        assert!(matches!(
+            number("(number->int (/ -7 2))", &mut env),
+            Number::Int(-3)
+        ));
+        assert!(matches!(
+            number("(div-euclid (/ -7 2) 1)", &mut env),
+            Number::Int(-4)
+        ));
+
+        assert_eq!(lisp!("(= (/ 1 2) 0.5)", &mut env), Expression::Bool(true));
+        assert_eq!(
+            lisp!("(< (/ 1 3) (/ 1 2))", &mut env),
+            Expression::Bool(true)
+        );
+        assert!(matches!(number("(/ 1 3)", &mut env), Number::Ratio(_)));
+        assert!(matches!(
+            eval_err("(numerator 0.5)", &mut env),
+            DracaError::TypeMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn literals() {
+        let mut env = setup_env();
+
+        assert!(matches!(
+            literal("1_000_000", &mut env),
+            Number::Int(1_000_000)
+        ));
+        assert!(matches!(literal("+3", &mut env), Number::Int(3)));
+        assert!(matches!(literal("0xff", &mut env), Number::Int(255)));
+        assert!(matches!(literal("-0o17", &mut env), Number::Int(-15)));
+        assert!(matches!(literal("0b1010", &mut env), Number::Int(10)));
+        assert!(matches!(literal("1e9", &mut env), Number::Float(1e9)));
+        assert!(matches!(
+            literal("-2.5E-3", &mut env),
+            Number::Float(-0.0025)
+        ));
+        assert!(matches!(literal(".5", &mut env), Number::Float(0.5)));
+        assert!(matches!(literal("-.5", &mut env), Number::Float(-0.5)));
+        assert!(matches!(
+            literal("-inf.0", &mut env),
+            Number::Float(f64::NEG_INFINITY)
+        ));
+        assert!(matches!(literal("+nan.0", &mut env), Number::Float(n) if n.is_nan()));
+        assert_eq!(
+            literal("0xffff_ffff_ffff_ffff_ffff", &mut env).to_string(),
+            "1208925819614629174706175"
+        );
+
+        // A number that runs on into other symbol characters is a symbol.
+        assert_eq!(lisp!("(- 5 +3)", &mut env), int!(2));
+        assert_eq!(lisp!("(quote +inf.0x)", &mut env).to_string(), "+inf.0x");
+        // Infinity and NaN need a sign.
+        assert_eq!(
+            lisp!("(quote inf.0)", &mut env),
+            Expression::symbol("inf.0")
+        );
+        assert_eq!(
+            lisp!("(quote nan.0)", &mut env),
+            Expression::symbol("nan.0")
+        );
+
+        assert_eq!(literal("1/3", &mut env).to_string(), "1/3");
+        assert_eq!(literal("-2/6", &mut env).to_string(), "-1/3");
+        assert!(matches!(literal("4/2", &mut env), Number::Int(2)));
+        assert!(crate::parser::parse("(quote 1/0)").is_err());
+
+        for text in [
+            "42",
+            "-7",
+            "0xffff_ffff_ffff_ffff_ffff",
+            "-1/3",
+            "22/7",
+            "1e20",
+            "1e-7",
+            "0.1",
+            "-3.0",
+            "+inf.0",
+            "-inf.0",
+            "+nan.0",
+        ] {
+            let number = literal(text, &mut env);
+            let printed = number.to_string();
+            let read_back = literal(&printed, &mut env);
+
+            assert_eq!(read_back.to_string(), printed);
+            assert_eq!(read_back.type_name(), number.type_name());
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let mut env = setup_env();
+
+        assert!(matches!(
+            number("(number->int -2.7)", &mut env),
+            Number::Int(-2)
+        ));
+        assert!(matches!(
+            number("(exact->inexact 2)", &mut env),
+            Number::Float(2.0)
+        ));
+        assert_eq!(
+            lisp!("(string->number \"42\")", &mut env),
+            Expression::ok(int!(42))
+        );
+    }
+}
@@ -1,8 +1,14 @@
+pub mod char;
 pub mod cmp;
+pub mod enums;
+pub mod errors;
+pub mod io;
 pub mod list;
 pub mod macros;
 pub mod math;
+pub mod option;
 pub mod string;
+pub mod structs;
 pub mod sys;
 
 #[macro_export]
@@ -15,10 +21,15 @@ macro_rules! lisp {
 
 #[macro_export]
 macro_rules! empty_quoted_list {
-    () => {{ $crate::parser::Expression::Quoted(Box::new($crate::parser::Expression::List(vec![]))) }};
+    () => {{ $crate::parser::Expression::Quoted(Box::new($crate::parser::Expression::list([]))) }};
 }
 
 #[macro_export]
 macro_rules! num {
-    ($num:expr) => {{ $crate::parser::Expression::Number($num) }};
+    ($num:expr) => {{ $crate::parser::Expression::Number($crate::number::Number::Float($num)) }};
+}
+
+#[macro_export]
+macro_rules! int {
+    ($num:expr) => {{ $crate::parser::Expression::Number($crate::number::Number::Int($num)) }};
 }
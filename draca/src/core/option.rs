@@ -0,0 +1,80 @@
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::Expression,
+};
+
+macro_rules! constructors {
+    ( $( ($name:ident, $draca:expr) ),* $(,)? ) => {
+        $(
+            pub fn $name(args: &[Expression]) -> Result<Expression, DracaError> {
+                match args {
+                    [value] => Ok(Expression::$name(value.clone())),
+                    _ => Err(DracaError::arity($draca, ArgCount::Exactly(1), args.len())),
+                }
+            }
+        )*
+    };
+}
+
+constructors![(some, "Some"), (ok, "Ok"), (err, "Err"),];
+
+#[cfg(test)]
+mod test {
+    use crate::{env::Environment, int, lisp};
+
+    use super::*;
+
+    fn setup_env() -> Environment {
+        Environment::empty().core().stdlib().build()
+    }
+
+    #[test]
+    fn option() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(std::option::map (Some 1) (lambda (x) (+ x 1)))", &mut env),
+            Expression::some(int!(2))
+        );
+        assert_eq!(
+            lisp!(
+                "(std::option::and-then None (lambda (x) (Some x)))",
+                &mut env
+            ),
+            Expression::none()
+        );
+        assert_eq!(lisp!("(std::option::unwrap-or None 3)", &mut env), int!(3));
+        assert_eq!(
+            lisp!("(std::option::ok-or None \"missing\")", &mut env),
+            Expression::err(Expression::string("missing"))
+        );
+    }
+
+    #[test]
+    fn result() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(std::conv::string->number \"12\")", &mut env),
+            Expression::ok(int!(12))
+        );
+        assert_eq!(
+            lisp!(
+                "(std::result::and-then (Ok 2) (lambda (x) (Err x)))",
+                &mut env
+            ),
+            Expression::err(int!(2))
+        );
+        assert_eq!(
+            lisp!(
+                "(std::result::unwrap-or (std::conv::string->number \"twelve\") 0)",
+                &mut env
+            ),
+            int!(0)
+        );
+        assert_eq!(
+            lisp!("(list (Ok 1) (Err \"no\"))", &mut env).to_string(),
+            "(Ok(1) Err(\"no\"))"
+        );
+    }
+}
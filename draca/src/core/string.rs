@@ -1,23 +1,24 @@
-use crate::{core::list::extract_list, parser::Expression};
+use crate::{
+    core::list::extract_list,
+    error::{ArgCount, DracaError},
+    number::{BigInt, Number},
+    parser::Expression,
+};
 
-pub fn as_list(args: &[Expression]) -> Result<Expression, String> {
+pub fn as_list(args: &[Expression]) -> Result<Expression, DracaError> {
     match args {
         [first, ..] => {
             if let Expression::String(str) = first {
-                Ok(Expression::List(
-                    str.chars()
-                        .map(|c| Expression::String(String::from(c)))
-                        .collect(),
-                ))
+                Ok(Expression::list(str.chars().map(Expression::Char)))
             } else {
-                Err("`string->list` accepts a string".into())
+                Err(DracaError::type_mismatch("string", first.type_name()))
             }
         }
-        _ => Err("`string->list` requires one argument".into()),
+        _ => Err(DracaError::arity("string->list", ArgCount::Exactly(1), 0)),
     }
 }
 
-pub fn from_list(args: &[Expression]) -> Result<Expression, String> {
+pub fn from_list(args: &[Expression]) -> Result<Expression, DracaError> {
     match args {
         [first, ..] => {
             let lst = extract_list(first)?;
@@ -25,6 +26,30 @@ pub fn from_list(args: &[Expression]) -> Result<Expression, String> {
                 lst.iter().map(Expression::fmt_string).collect(),
             ))
         }
-        _ => Err("`list->string` requires one argument".into()),
+        _ => Err(DracaError::arity("list->string", ArgCount::Exactly(1), 0)),
+    }
+}
+
+/// Parse a number, returning `Ok` with it or `Err` with why it could not be parsed.
+pub fn to_number(args: &[Expression]) -> Result<Expression, DracaError> {
+    match args {
+        [Expression::String(str)] => {
+            let str = str.trim();
+            let number = match BigInt::parse(str) {
+                Some(n) => Ok(Number::from_big(n)),
+                None => str.parse().map(Number::Float),
+            };
+
+            Ok(match number {
+                Ok(n) => Expression::ok(Expression::Number(n)),
+                Err(e) => Expression::err(Expression::String(format!("`{str}`: {e}"))),
+            })
+        }
+        [other] => Err(DracaError::type_mismatch("string", other.type_name())),
+        _ => Err(DracaError::arity(
+            "string->number",
+            ArgCount::Exactly(1),
+            args.len(),
+        )),
     }
 }
@@ -0,0 +1,118 @@
+//! The functions behind the constructors, accessors, predicates and updaters that `defstruct!`
+//! defines. Each takes an instance of the struct (with every field `nil`) as a prototype, so it
+//! knows which struct it is working on.
+
+use std::rc::Rc;
+
+use crate::{
+    error::{ArgCount, DracaError},
+    parser::{Expression, Struct},
+};
+
+fn prototype<'a>(args: &'a [Expression], name: &str) -> Result<&'a Rc<Struct>, DracaError> {
+    match args.first() {
+        Some(Expression::Struct(proto)) => Ok(proto),
+        Some(other) => Err(DracaError::type_mismatch("struct", other.type_name())),
+        None => Err(DracaError::arity(name, ArgCount::AtLeast(1), 0)),
+    }
+}
+
+/// Check that `value` is an instance of the same struct as `proto`. Structs are told apart by
+/// definition rather than by shape, so redefining a struct rejects values made before.
+fn instance<'a>(proto: &Struct, value: &'a Expression) -> Result<&'a Rc<Struct>, DracaError> {
+    match value {
+        Expression::Struct(st) if Rc::ptr_eq(&st.ty, &proto.ty) => Ok(st),
+        other => Err(DracaError::type_mismatch(
+            format!("struct `{}`", proto.ty.name),
+            other.type_name(),
+        )),
+    }
+}
+
+/// Find which field `field` names, accepting both `x` and `'x`.
+fn field_index(proto: &Struct, field: &Expression) -> Result<usize, DracaError> {
+    let name = match field {
+        Expression::Symbol(s) => s,
+        Expression::Quoted(box Expression::Symbol(s)) => s,
+        other => {
+            return Err(DracaError::type_mismatch("field name", other.type_name()));
+        }
+    };
+
+    proto
+        .ty
+        .fields
+        .iter()
+        .position(|field| **field == **name)
+        .ok_or_else(|| {
+            DracaError::other(format!("struct `{}` has no field `{name}`", proto.ty.name))
+        })
+}
+
+/// `(make proto values...)`
+pub fn make(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "make")?;
+    let values = &args[1..];
+
+    if values.len() != proto.ty.fields.len() {
+        return Err(DracaError::arity(
+            format!("make-{}", proto.ty.name),
+            ArgCount::Exactly(proto.ty.fields.len()),
+            values.len(),
+        ));
+    }
+
+    Ok(Expression::Struct(Rc::new(Struct {
+        ty: Rc::clone(&proto.ty),
+        values: values.to_vec(),
+    })))
+}
+
+/// `(get proto field value)`
+pub fn get(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "get")?;
+
+    match &args[1..] {
+        [field, value] => {
+            let st = instance(proto, value)?;
+            Ok(st.values[field_index(proto, field)?].clone())
+        }
+        rest => Err(DracaError::arity(
+            "get",
+            ArgCount::Exactly(3),
+            rest.len() + 1,
+        )),
+    }
+}
+
+/// `(is? proto value)`
+pub fn is(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "is?")?;
+
+    match &args[1..] {
+        [value] => Ok(Expression::Bool(instance(proto, value).is_ok())),
+        rest => Err(DracaError::arity(
+            "is?",
+            ArgCount::Exactly(2),
+            rest.len() + 1,
+        )),
+    }
+}
+
+/// `(with proto value field new)`: a copy of `value` with `field` set to `new`.
+pub fn with(args: &[Expression]) -> Result<Expression, DracaError> {
+    let proto = prototype(args, "with")?;
+
+    match &args[1..] {
+        [value, field, new] => {
+            let mut st = Struct::clone(instance(proto, value)?);
+            st.values[field_index(proto, field)?] = new.clone();
+            Ok(Expression::Struct(Rc::new(st)))
+        }
+        rest => Err(DracaError::arity(
+            "with",
+            ArgCount::Exactly(4),
+            rest.len() + 1,
+        )),
+    }
+}
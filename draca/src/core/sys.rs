@@ -1,12 +1,17 @@
-use crate::parser::Expression;
+use crate::{
+    backtrace,
+    error::{ArgCount, DracaError},
+    int,
+    parser::Expression,
+};
 
-pub fn exit(args: &[Expression]) -> Result<Expression, String> {
-    let num: Result<i32, String> = match args {
+pub fn exit(args: &[Expression]) -> Result<Expression, DracaError> {
+    let num: Result<i32, DracaError> = match args {
         [arg] | [_, arg] | [_, arg, ..] => {
             if let Expression::Number(first) = arg {
-                Ok(*first as i32)
+                Ok(first.as_i64() as i32)
             } else {
-                Err("invalid arguments, not numbers".into())
+                Err(DracaError::type_mismatch("number", arg.type_name()))
             }
         }
         [] => Ok(0),
@@ -14,3 +19,18 @@ pub fn exit(args: &[Expression]) -> Result<Expression, String> {
 
     std::process::exit(num?)
 }
+
+/// The procedure calls currently being evaluated, innermost first, as `(name argument-count)`.
+pub fn backtrace(args: &[Expression]) -> Result<Expression, DracaError> {
+    if !args.is_empty() {
+        return Err(DracaError::arity(
+            "backtrace",
+            ArgCount::Exactly(0),
+            args.len(),
+        ));
+    }
+
+    Ok(Expression::list(backtrace::current().frames().iter().map(
+        |frame| Expression::list([Expression::symbol(&*frame.name), int!(frame.args as i64)]),
+    )))
+}
@@ -1,10 +1,13 @@
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::f64::consts::{E, PI};
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
 
+use crate::error::DracaError;
 use crate::eval::eval;
-use crate::parser::parse;
-use crate::{core, parser::Expression};
+use crate::parser::parse_source;
+use crate::{core, int, num, parser::Expression};
 
 const STDLIB: &str = include_str!(concat!(env!("OUT_DIR"), "/stdlib.dr"));
 
@@ -60,6 +63,15 @@ impl Namespace {
     pub fn as_str(&self, join: &str) -> String {
         self.frags.join(join)
     }
+
+    /// Check whether this namespace is spelled `path`, e.g. `std::list`.
+    pub fn is(&self, path: &str) -> bool {
+        if path.is_empty() {
+            self.frags.is_empty()
+        } else {
+            self.frags.iter().map(String::as_str).eq(path.split("::"))
+        }
+    }
 }
 
 impl<I, T> From<I> for Namespace
@@ -139,7 +151,11 @@ impl NamespaceItem {
 
 impl Display for NamespaceItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}::{}", self.frags, self.target)
+        if self.frags.frags.is_empty() {
+            write!(f, "{}", self.target)
+        } else {
+            write!(f, "{}::{}", self.frags, self.target)
+        }
     }
 }
 
@@ -149,18 +165,149 @@ impl PartialEq<String> for NamespaceItem {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+/// Global bindings, grouped by their unqualified name and then by namespace, so a lookup never has
+/// to build a [`NamespaceItem`].
+type Globals = HashMap<String, BTreeMap<Namespace, Binding>>;
+
+/// A bound value, and whether `set!` may change it.
+#[derive(Clone)]
+struct Binding {
+    value: Expression,
+    mutable: bool,
+}
+
+/// A single lexical scope: the bindings of one `let` or one procedure call.
+#[derive(Default)]
+struct Frame {
+    bindings: RefCell<HashMap<String, Binding>>,
+    parent: Option<Rc<Frame>>,
+    /// Whether this frame holds the bindings of a `letrec`, see [`Environment::recursive`].
+    recursive: bool,
+}
+
+impl Frame {
+    /// Find the innermost frame, starting from this one, that binds `name`.
+    fn find(&self, name: &str) -> Option<&Frame> {
+        let mut frame = Some(self);
+
+        while let Some(current) = frame {
+            if current.bindings.borrow().contains_key(name) {
+                return Some(current);
+            }
+            frame = current.parent.as_deref();
+        }
+
+        None
+    }
+
+    /// Look `name` up in this frame and then in every enclosing one.
+    fn get(&self, name: &str) -> Option<Expression> {
+        self.find(name)
+            .map(|frame| frame.bindings.borrow()[name].value.clone())
+    }
+
+    fn names(&self) -> Vec<String> {
+        let mut names = self.bindings.borrow().keys().cloned().collect::<Vec<_>>();
+        if let Some(parent) = &self.parent {
+            names.extend(parent.names());
+        }
+        names
+    }
+
+    /// Unbind everything in `frame` if all that keeps it alive is `others` references and the
+    /// procedures bound in it that close over it, and nothing else refers to those procedures.
+    fn release_cycle(frame: &Rc<Self>, others: usize) {
+        let Ok(bindings) = frame.bindings.try_borrow() else {
+            return;
+        };
+
+        let mut closures = 0;
+        for binding in bindings.values() {
+            if let Expression::Function(proc) = &binding.value
+                && proc
+                    .env
+                    .frame
+                    .as_ref()
+                    .is_some_and(|env| Rc::ptr_eq(env, frame))
+            {
+                if Rc::strong_count(proc) > 1 {
+                    return;
+                }
+                closures += 1;
+            }
+        }
+        drop(bindings);
+
+        if Rc::strong_count(frame) == closures + others
+            && let Ok(mut bindings) = frame.bindings.try_borrow_mut()
+        {
+            // Take them out first, so the procedures are not dropped while the frame is borrowed.
+            let bindings = std::mem::take(&mut *bindings);
+            drop(bindings);
+        }
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        if let Some(parent) = &self.parent
+            && parent.recursive
+        {
+            Self::release_cycle(parent, 1);
+        }
+    }
+}
+
+/// The environment an expression is evaluated in.
+///
+/// Global definitions live in one table shared by every clone of the environment, so they are
+/// resolved when they are used rather than when a closure is created. Lexical bindings (`let` and
+/// procedure parameters) live in a chain of reference-counted [`Frame`]s, so cloning an
+/// environment for a closure or a call never copies any bindings.
+#[derive(Clone)]
 pub struct Environment {
-    contents: BTreeMap<NamespaceItem, Expression>,
-    in_scope: Vec<Namespace>,
+    globals: Rc<RefCell<Globals>>,
+    /// The innermost lexical scope, or [`None`] at the top level.
+    frame: Option<Rc<Frame>>,
+    in_scope: Rc<Vec<Namespace>>,
 }
 
 #[allow(dead_code)]
 impl Environment {
     pub fn empty() -> Self {
         Self {
-            contents: BTreeMap::new(),
-            in_scope: vec![],
+            globals: Rc::default(),
+            frame: None,
+            in_scope: Rc::new(vec![]),
+        }
+    }
+
+    /// Create a lexical scope nested in this environment.
+    ///
+    /// Anything inserted into the returned environment shadows, but does not touch, the bindings
+    /// of `self`.
+    pub fn nested(&self) -> Self {
+        self.nested_frame(false)
+    }
+
+    /// Like [`Self::nested`], for the bindings of a `letrec`.
+    ///
+    /// The procedures bound there close over the frame that holds them, a cycle reference counting
+    /// would never free. So whenever a scope nested in it ends, such as the body or a call to one
+    /// of the procedures, the frame is emptied if nothing else refers to it or them.
+    pub fn recursive(&self) -> Self {
+        self.nested_frame(true)
+    }
+
+    fn nested_frame(&self, recursive: bool) -> Self {
+        Self {
+            globals: Rc::clone(&self.globals),
+            frame: Some(Rc::new(Frame {
+                bindings: RefCell::default(),
+                parent: self.frame.clone(),
+                recursive,
+            })),
+            in_scope: Rc::clone(&self.in_scope),
         }
     }
 
@@ -168,76 +315,160 @@ impl Environment {
         &self.in_scope
     }
 
-    pub fn full_path_and_name(&self) -> Vec<(String, &str)> {
-        self.contents
-            .keys()
-            .map(|key| (key.to_string(), key.target()))
-            .collect()
+    pub fn full_path_and_name(&self) -> Vec<(String, String)> {
+        let globals = self.globals.borrow();
+        let globals = globals.iter().flat_map(|(target, namespaces)| {
+            namespaces
+                .keys()
+                .map(move |ns| (ns.join(target).to_string(), target.clone()))
+        });
+        let locals = self
+            .frame
+            .iter()
+            .flat_map(|frame| frame.names())
+            .map(|name| (name.clone(), name));
+
+        globals.chain(locals).collect()
     }
 
-    pub fn values(&self) -> Vec<&str> {
-        let keys = self.contents.keys();
-        keys.into_iter().map(NamespaceItem::target).collect()
+    pub fn values(&self) -> Vec<String> {
+        self.full_path_and_name()
+            .into_iter()
+            .map(|(_, target)| target)
+            .collect()
     }
 
     pub fn with_scope(mut self, ns: Namespace) -> Self {
-        self.in_scope.push(ns);
+        self.add_scope(ns);
         self
     }
 
     pub fn add_scope<T: Into<Namespace>>(&mut self, ns: T) {
-        self.in_scope.push(ns.into());
+        Rc::make_mut(&mut self.in_scope).push(ns.into());
     }
 
     pub fn insert_item(&mut self, item: NamespaceItem, val: Expression) {
-        self.contents.insert(item, val);
+        self.insert(&item.to_string(), val);
     }
 
-    pub fn insert(&mut self, key: impl Into<NamespaceItem>, val: Expression) {
-        self.contents.insert(key.into(), val);
+    /// Bind `key` in the innermost scope: the current frame if there is one, otherwise the global
+    /// table.
+    pub fn insert(&mut self, key: &str, val: Expression) {
+        self.bind(key, val, false);
     }
 
-    pub fn get_namespace_str(&self, target: &str) -> Option<String> {
-        if self.contents.contains_key(&NamespaceItem::from(target)) {
-            return Some(target.to_string());
-        }
+    /// Like [`Self::insert`], but the binding can be changed later with [`Self::set`].
+    pub fn insert_mut(&mut self, key: &str, val: Expression) {
+        self.bind(key, val, true);
+    }
 
-        for ns in &self.in_scope {
-            let try_item = NamespaceItem {
-                frags: ns.clone(),
-                target: target.to_string(),
-            };
+    fn bind(&mut self, key: &str, value: Expression, mutable: bool) {
+        let binding = Binding { value, mutable };
 
-            if self.contents.contains_key(&try_item) {
-                return Some(try_item.to_string());
+        match &self.frame {
+            Some(frame) => {
+                frame.bindings.borrow_mut().insert(key.to_string(), binding);
+            }
+            None => {
+                let NamespaceItem { frags, target } = NamespaceItem::from(key);
+                self.globals
+                    .borrow_mut()
+                    .entry(target)
+                    .or_default()
+                    .insert(frags, binding);
             }
         }
+    }
 
-        for item in self.contents.keys() {
-            if item.target == target {
-                return Some(item.to_string());
-            }
+    /// Change the value of the existing binding `key` resolves to, wherever it is, so everything
+    /// that shares it sees the change.
+    pub fn set(&mut self, key: &str, val: Expression) -> Result<(), DracaError> {
+        if let Some(frame) = self.frame.as_ref().and_then(|frame| frame.find(key)) {
+            let mut bindings = frame.bindings.borrow_mut();
+            return Self::assign(bindings.get_mut(key), key, val);
         }
 
-        None
+        let mut globals = self.globals.borrow_mut();
+        let Some((ns, _)) = self.resolve(&globals, key) else {
+            return Err(DracaError::UndefinedSymbol(key.to_string()));
+        };
+
+        let ns = ns.clone();
+        let target = key.rsplit("::").next().unwrap_or(key);
+        let binding = globals
+            .get_mut(target)
+            .and_then(|namespaces| namespaces.get_mut(&ns));
+
+        Self::assign(binding, key, val)
+    }
+
+    fn assign(binding: Option<&mut Binding>, key: &str, val: Expression) -> Result<(), DracaError> {
+        match binding {
+            Some(binding) if binding.mutable => {
+                binding.value = val;
+                Ok(())
+            }
+            Some(_) => Err(DracaError::Immutable(key.to_string())),
+            None => Err(DracaError::UndefinedSymbol(key.to_string())),
+        }
     }
 
-    pub fn get(&self, key: &str) -> Option<&Expression> {
-        if let Some(found) = self.contents.get(&NamespaceItem::from(key)) {
+    /// Find the binding `key` resolves to in the global table, either by its full path or through
+    /// one of the namespaces in scope.
+    ///
+    /// A name defined outside any namespace always wins. Otherwise the namespace brought into scope
+    /// most recently wins, so `(require option)` lets a user's `option::Some` shadow the
+    /// `std::prelude::Some` that [`Self::core`] brought in first.
+    fn resolve<'g>(&self, globals: &'g Globals, key: &str) -> Option<(&'g Namespace, &'g Binding)> {
+        let (path, target) = key.rsplit_once("::").unwrap_or(("", key));
+        let candidates = globals.get(target)?;
+
+        if let Some(found) = candidates.iter().find(|(ns, _)| ns.is(path)) {
             return Some(found);
         }
 
-        for ns in &self.in_scope {
-            let try_item = NamespaceItem {
-                frags: ns.clone(),
-                target: key.to_string(),
-            };
-            if let Some(found) = self.contents.get(&try_item) {
-                return Some(found);
-            }
+        if !path.is_empty() {
+            return None;
         }
 
-        None
+        self.in_scope
+            .iter()
+            .rev()
+            .find_map(|ns| candidates.get_key_value(ns))
+    }
+
+    pub fn get_namespace_str(&self, target: &str) -> Option<String> {
+        if self
+            .frame
+            .as_ref()
+            .and_then(|frame| frame.get(target))
+            .is_some()
+        {
+            return Some(target.to_string());
+        }
+
+        let globals = self.globals.borrow();
+
+        if let Some((ns, _)) = self.resolve(&globals, target) {
+            return Some(
+                ns.join(target.rsplit("::").next().unwrap_or(target))
+                    .to_string(),
+            );
+        }
+
+        globals
+            .get(target)
+            .and_then(|namespaces| namespaces.keys().next())
+            .map(|ns| ns.join(target).to_string())
+    }
+
+    pub fn get(&self, key: &str) -> Option<Expression> {
+        if let Some(found) = self.frame.as_ref().and_then(|frame| frame.get(key)) {
+            return Some(found);
+        }
+
+        self.resolve(&self.globals.borrow(), key)
+            .map(|(_, found)| found.value.clone())
     }
 
     pub fn core(mut self) -> Self {
@@ -259,6 +490,24 @@ impl Environment {
             ("std::list::list", fn => core::list::list),
             ("std::list::empty?", fn => core::list::is_empty),
             ("std::list::len", fn => core::list::len),
+            ("std::list::range", fn => core::list::range),
+        ];
+
+        // CHARACTERS //
+
+        self.add_scope(["std", "char"]);
+
+        env_insert![self =>
+            ("std::char::alphabetic?", fn => core::char::is_alphabetic),
+            ("std::char::numeric?", fn => core::char::is_numeric),
+            ("std::char::alphanumeric?", fn => core::char::is_alphanumeric),
+            ("std::char::whitespace?", fn => core::char::is_whitespace),
+            ("std::char::upper?", fn => core::char::is_upper),
+            ("std::char::lower?", fn => core::char::is_lower),
+            ("std::char::to-upper", fn => core::char::to_upper),
+            ("std::char::to-lower", fn => core::char::to_lower),
+            ("std::char::char->int", fn => core::char::to_int),
+            ("std::char::int->char", fn => core::char::from_int),
         ];
 
         // CONVERSIONS //
@@ -268,6 +517,9 @@ impl Environment {
         env_insert![self =>
             ("std::conv::string->list", fn => core::string::as_list),
             ("std::conv::list->string", fn => core::string::from_list),
+            ("std::conv::string->number", fn => core::string::to_number),
+            ("std::conv::number->int", fn => core::math::to_int),
+            ("std::conv::exact->inexact", fn => core::math::to_inexact),
         ];
 
         // MACROS //
@@ -278,12 +530,62 @@ impl Environment {
             ("std::macros::panic",  fn => core::macros::panic),
             ("std::macros::format",  fn => core::macros::format),
             ("std::macros::println",  fn => core::macros::println),
+            ("std::macros::gensym",  fn => core::macros::gensym),
+        ];
+
+        // OPTIONS AND RESULTS //
+
+        self.add_scope(["std", "prelude"]);
+
+        env_insert![self =>
+            ("std::option::Some", fn => core::option::some),
+            ("std::option::None", const => Expression::none()),
+            ("std::result::Ok", fn => core::option::ok),
+            ("std::result::Err", fn => core::option::err),
+            ("std::prelude::Some", fn => core::option::some),
+            ("std::prelude::None", const => Expression::none()),
+            ("std::prelude::Ok", fn => core::option::ok),
+            ("std::prelude::Err", fn => core::option::err),
+        ];
+
+        // ERRORS //
+
+        self.add_scope(["std", "error"]);
+
+        env_insert![self =>
+            ("std::error::error->kind", fn => core::errors::kind),
+            ("std::error::error->message", fn => core::errors::message),
+            ("std::error::error?", fn => core::errors::is_error),
+        ];
+
+        // INPUT AND OUTPUT //
+
+        env_insert![self =>
+            ("std::io::read-line", fn => core::io::read_line),
+        ];
+
+        // STRUCTS //
+
+        env_insert![self =>
+            ("std::structs::make", fn => core::structs::make),
+            ("std::structs::get", fn => core::structs::get),
+            ("std::structs::is?", fn => core::structs::is),
+            ("std::structs::with", fn => core::structs::with),
+        ];
+
+        // ENUMS //
+
+        env_insert![self =>
+            ("std::enums::make", fn => core::enums::make),
+            ("std::enums::is?", fn => core::enums::is),
+            ("std::enums::expect", fn => core::enums::expect),
         ];
 
         // SYSTEM COMPONENTS //
 
         env_insert![self =>
             ("std::sys::exit",  fn => core::sys::exit),
+            ("std::sys::backtrace",  fn => core::sys::backtrace),
         ];
 
         // NUMERICAL COMPARISONS //
@@ -312,17 +614,40 @@ impl Environment {
             ("std::math::rem",         fn => core::math::rem),
             ("std::math::pow",         fn => core::math::pow),
             ("std::math::ash",         fn => core::math::ash),
-            ("std::math::consts::pi",  const => Expression::Number(PI)),
-            ("std::math::consts::e",   const => Expression::Number(E)),
-            ("std::math::consts::NUM-MAX",   const => Expression::Number(f64::MAX)),
-            ("std::math::consts::NUM-MIN",   const => Expression::Number(f64::MIN)),
+            ("std::math::div-euclid",  fn => core::math::div_euclid),
+            ("std::math::rem-euclid",  fn => core::math::rem_euclid),
+            ("std::math::numerator",   fn => core::math::numerator),
+            ("std::math::denominator", fn => core::math::denominator),
+            ("std::math::checked-add", fn => core::math::checked_add),
+            ("std::math::checked-sub", fn => core::math::checked_sub),
+            ("std::math::checked-mul", fn => core::math::checked_mul),
+            ("std::math::checked-div", fn => core::math::checked_div),
+            ("std::math::checked-rem", fn => core::math::checked_rem),
+            ("std::math::checked-pow", fn => core::math::checked_pow),
+            ("std::math::wrapping-add", fn => core::math::wrapping_add),
+            ("std::math::wrapping-sub", fn => core::math::wrapping_sub),
+            ("std::math::wrapping-mul", fn => core::math::wrapping_mul),
+            ("std::math::wrapping-div", fn => core::math::wrapping_div),
+            ("std::math::wrapping-rem", fn => core::math::wrapping_rem),
+            ("std::math::wrapping-pow", fn => core::math::wrapping_pow),
+            ("std::math::saturating-add", fn => core::math::saturating_add),
+            ("std::math::saturating-sub", fn => core::math::saturating_sub),
+            ("std::math::saturating-mul", fn => core::math::saturating_mul),
+            ("std::math::saturating-div", fn => core::math::saturating_div),
+            ("std::math::saturating-pow", fn => core::math::saturating_pow),
+            ("std::math::consts::pi",  const => num!(PI)),
+            ("std::math::consts::e",   const => num!(E)),
+            ("std::math::consts::NUM-MAX",   const => num!(f64::MAX)),
+            ("std::math::consts::NUM-MIN",   const => num!(f64::MIN)),
+            ("std::math::consts::INT-MAX",   const => int!(i64::MAX)),
+            ("std::math::consts::INT-MIN",   const => int!(i64::MIN)),
         ];
 
         self
     }
 
     pub fn stdlib(mut self) -> Self {
-        let parsed_stdlib = parse(STDLIB.trim()).expect("stdlib is broken");
+        let parsed_stdlib = parse_source("<stdlib>", STDLIB).expect("stdlib is broken");
 
         for expr in parsed_stdlib {
             let _ = eval(expr, &mut self);
@@ -336,10 +661,40 @@ impl Environment {
     }
 }
 
-pub fn run_file(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+// Bindings can hold procedures which hold this environment, so frames are compared by identity and
+// only printed by name to avoid walking in circles.
+
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        let same_frame = match (&self.frame, &other.frame) {
+            (Some(lhs), Some(rhs)) => Rc::ptr_eq(lhs, rhs),
+            (None, None) => true,
+            _ => false,
+        };
+
+        Rc::ptr_eq(&self.globals, &other.globals) && same_frame && self.in_scope == other.in_scope
+    }
+}
+
+impl PartialOrd for Environment {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (self == other).then_some(std::cmp::Ordering::Equal)
+    }
+}
+
+impl Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Environment")
+            .field("locals", &self.frame.as_ref().map(|frame| frame.names()))
+            .field("in_scope", &self.in_scope)
+            .finish_non_exhaustive()
+    }
+}
+
+pub fn run_file(path: &str) -> Result<(), DracaError> {
     let text = std::fs::read_to_string(path)?;
 
-    let parsed = parse(text.trim())?;
+    let parsed = parse_source(path, &text)?;
 
     let mut env = Environment::empty().core().stdlib().build();
 
@@ -0,0 +1,274 @@
+use std::{borrow::Cow, fmt::Display};
+
+use crate::{
+    backtrace::Backtrace,
+    parser::{Expression, Rule},
+    source::Span,
+};
+
+/// How many arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgCount {
+    Exactly(usize),
+    AtLeast(usize),
+    Between(usize, usize),
+}
+
+impl Display for ArgCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exactly(n) => write!(f, "{n}"),
+            Self::AtLeast(n) => write!(f, "at least {n}"),
+            Self::Between(min, max) => write!(f, "{min} to {max}"),
+        }
+    }
+}
+
+/// Everything that can go wrong while parsing or evaluating Draca.
+#[derive(Debug)]
+pub enum DracaError {
+    /// A symbol has no binding in the environment.
+    UndefinedSymbol(String),
+    /// A value of the wrong type was passed somewhere.
+    TypeMismatch {
+        /// Borrowed for builtin kinds, owned when it names a user type such as struct `point`.
+        expected: Cow<'static, str>,
+        found: &'static str,
+    },
+    /// `set!` was used on a binding that was not declared `mut`.
+    Immutable(String),
+    /// A function was called with the wrong number of arguments.
+    Arity {
+        name: String,
+        expected: ArgCount,
+        got: usize,
+    },
+    /// Something that is not a function was called.
+    NotCallable(String),
+    /// A special form was used with malformed syntax.
+    Syntax(String),
+    /// The source text could not be parsed.
+    Parse(Box<pest_consume::Error<Rule>>),
+    Io(std::io::Error),
+    /// No arm of a `match` accepted the value.
+    NoMatch(String),
+    /// Raised by `std::macros::panic`.
+    UserPanic(String),
+    /// An integer overflowed, or was divided by zero.
+    Arithmetic(String),
+    /// Any other runtime error.
+    Other(String),
+    /// Not an error: unwinds to the innermost procedure call, which returns the value. Raised by
+    /// `return` and `?`.
+    Return(Box<Expression>),
+    /// Not an error: leaves the innermost loop, or the innermost block with this label, which
+    /// gives back the value. Raised by `break`.
+    Break {
+        label: Option<String>,
+        value: Box<Expression>,
+    },
+    /// Not an error: skips to the next iteration of the innermost loop. Raised by `continue`.
+    Continue,
+    /// An error raised while evaluating the expression at `span`.
+    Located {
+        span: Span,
+        error: Box<DracaError>,
+    },
+    /// An error that unwound through the Draca procedure calls in `backtrace`.
+    Traced {
+        error: Box<DracaError>,
+        backtrace: Backtrace,
+    },
+}
+
+impl DracaError {
+    pub fn syntax<T: Into<String>>(msg: T) -> Self {
+        Self::Syntax(msg.into())
+    }
+
+    pub fn other<T: Into<String>>(msg: T) -> Self {
+        Self::Other(msg.into())
+    }
+
+    pub fn type_mismatch<T: Into<Cow<'static, str>>>(expected: T, found: &'static str) -> Self {
+        Self::TypeMismatch {
+            expected: expected.into(),
+            found,
+        }
+    }
+
+    pub fn arity<T: Into<String>>(name: T, expected: ArgCount, got: usize) -> Self {
+        Self::Arity {
+            name: name.into(),
+            expected,
+            got,
+        }
+    }
+
+    /// Record that this error was raised at `span`, unless it already knows where it came from.
+    pub fn at(self, span: Option<Span>) -> Self {
+        match (self, span) {
+            (err @ (Self::Located { .. } | Self::Traced { .. }), _) | (err, None) => err,
+            (err, _) if err.is_control_flow() => err,
+            (err, Some(span)) => Self::Located {
+                span,
+                error: Box::new(err),
+            },
+        }
+    }
+
+    /// Record the procedure calls this error unwound through, innermost first.
+    pub fn with_backtrace(self, outer: Backtrace) -> Self {
+        match self {
+            Self::Traced {
+                error,
+                mut backtrace,
+            } => {
+                backtrace.extend(outer);
+                Self::Traced { error, backtrace }
+            }
+            err if outer.is_empty() => err,
+            err => Self::Traced {
+                error: Box::new(err),
+                backtrace: outer,
+            },
+        }
+    }
+
+    /// Whether this is not really an error, but a way of leaving a procedure or loop early, which
+    /// `try` must not catch.
+    pub fn is_control_flow(&self) -> bool {
+        matches!(
+            self.kind(),
+            Self::Return(_) | Self::Break { .. } | Self::Continue
+        )
+    }
+
+    /// A short name for what went wrong, like `user-panic`.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind() {
+            Self::UndefinedSymbol(_) => "undefined-symbol",
+            Self::TypeMismatch { .. } => "type-mismatch",
+            Self::Arity { .. } => "arity",
+            Self::Immutable(_) => "immutable",
+            Self::NotCallable(_) => "not-callable",
+            Self::Syntax(_) => "syntax",
+            Self::Parse(_) => "parse",
+            Self::Io(_) => "io",
+            Self::NoMatch(_) => "no-match",
+            Self::UserPanic(_) => "user-panic",
+            Self::Return(_) => "return",
+            Self::Break { .. } => "break",
+            Self::Continue => "continue",
+            Self::Arithmetic(_) => "arithmetic",
+            Self::Other(_) => "other",
+            Self::Located { .. } | Self::Traced { .. } => unreachable!("`kind` unwraps these"),
+        }
+    }
+
+    /// This error as a Draca `error` struct, for `catch` to bind.
+    pub fn to_value(&self) -> Expression {
+        let message = match self.kind() {
+            Self::UserPanic(msg) => msg.clone(),
+            kind => kind.to_string(),
+        };
+
+        Expression::error(self.kind_name(), message)
+    }
+
+    /// Where this error was raised, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Located { span, .. } => Some(*span),
+            Self::Traced { error, .. } => error.span(),
+            _ => None,
+        }
+    }
+
+    /// The procedure calls this error unwound through, if any.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            Self::Traced { backtrace, .. } => Some(backtrace),
+            _ => None,
+        }
+    }
+
+    /// The error itself, without its location or backtrace.
+    pub fn kind(&self) -> &Self {
+        match self {
+            Self::Located { error, .. } | Self::Traced { error, .. } => error.kind(),
+            err => err,
+        }
+    }
+
+    /// Like [`Self::kind`], but by value.
+    pub fn into_kind(self) -> Self {
+        match self {
+            Self::Located { error, .. } | Self::Traced { error, .. } => error.into_kind(),
+            err => err,
+        }
+    }
+}
+
+impl Display for DracaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedSymbol(sym) => write!(f, "undefined symbol `{sym}`"),
+            Self::TypeMismatch { expected, found } => {
+                write!(f, "mismatched types: expected {expected}, found {found}")
+            }
+            Self::Arity {
+                name,
+                expected,
+                got,
+            } => {
+                let plural = match expected {
+                    ArgCount::Exactly(1) | ArgCount::AtLeast(1) => "",
+                    _ => "s",
+                };
+                let were = if *got == 1 { "was" } else { "were" };
+                write!(
+                    f,
+                    "`{name}` takes {expected} argument{plural} but {got} {were} given"
+                )
+            }
+            Self::Immutable(name) => write!(
+                f,
+                "cannot assign twice to immutable binding `{name}`: declare it with `mut` to allow this"
+            ),
+            Self::NotCallable(what) => write!(f, "`{what}` is not a function"),
+            Self::Syntax(msg) => write!(f, "invalid syntax: {msg}"),
+            Self::Parse(err) => write!(f, "could not parse:\n{err}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::NoMatch(value) => write!(f, "no `match` arm matches `{value}`"),
+            Self::UserPanic(msg) => write!(f, "panicked: {msg}"),
+            Self::Arithmetic(msg) | Self::Other(msg) => write!(f, "{msg}"),
+            Self::Return(value) => write!(f, "cannot return `{value}` from outside a function"),
+            Self::Break { label: None, .. } => write!(f, "`break` outside of a loop"),
+            Self::Break {
+                label: Some(label), ..
+            } => write!(f, "`break '{label}` outside of a block labeled `'{label}`"),
+            Self::Continue => write!(f, "`continue` outside of a loop"),
+            Self::Located { span, error } => match span.location() {
+                Some(location) => write!(f, "{error}\n{location}"),
+                None => write!(f, "{error}"),
+            },
+            // The backtrace is long, so it is up to the caller to print it.
+            Self::Traced { error, .. } => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for DracaError {}
+
+impl From<Box<pest_consume::Error<Rule>>> for DracaError {
+    fn from(value: Box<pest_consume::Error<Rule>>) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl From<std::io::Error> for DracaError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
@@ -1,240 +1,552 @@
-use std::{borrow::Cow, fs};
+use std::{fs, rc::Rc};
 
 use crate::{
-    env::{Environment, Namespace, NamespaceItem},
-    parser::{Expression, Procedure},
+    backtrace::{self, CallFrame},
+    core,
+    env::{Environment, Namespace},
+    error::{ArgCount, DracaError},
+    parser::{Enum, EnumType, Expression, List, Params, Procedure, Struct, StructType, Symbol},
+    pattern,
 };
 
-pub fn eval(expr: Expression, env: &mut Environment) -> Result<Expression, String> {
-    let evaluated_expression = eval_expr(expr, env)?;
+/// Evaluate a top level form.
+///
+/// If evaluation fails, the error carries a backtrace of the procedure calls it unwound through.
+/// A `return`, `break` or `continue` that had nowhere to go comes out as a syntax error.
+pub fn eval(expr: Expression, env: &mut Environment) -> Result<Expression, DracaError> {
+    let depth = backtrace::depth();
 
-    Ok(evaluated_expression)
+    eval_expr(expr, env).map_err(|e| {
+        let e = if e.is_control_flow() {
+            DracaError::syntax(e.to_string())
+        } else {
+            e
+        };
+        e.with_backtrace(backtrace::unwind_to(depth))
+    })
 }
 
-pub(crate) fn eval_expr(expr: Expression, env: &mut Environment) -> Result<Expression, String> {
+/// The outcome of evaluating one step of an expression.
+///
+/// Forms that end in tail position (procedure bodies, `if` arms, `let` bodies) hand back the
+/// expression to evaluate next instead of recursing, so [`eval_expr`] can run it in a loop.
+enum Step {
+    /// Evaluation finished with a value.
+    Done(Expression),
+    /// Continue with this expression in the current environment.
+    Eval(Expression),
+    /// Continue with this expression in a new environment.
+    EvalIn(Expression, Environment),
+    /// Continue with the body of a procedure, whose arguments are bound in the environment.
+    Call(CallFrame, Rc<Procedure>, Environment),
+}
+
+/// Evaluate `expr` in `env`.
+///
+/// Procedure calls made from here push a frame onto the [`backtrace`], and tail calls replace it.
+/// The frame is popped once a value comes out, but left in place on an error so [`eval`] can
+/// report where the error came from.
+pub(crate) fn eval_expr(expr: Expression, env: &mut Environment) -> Result<Expression, DracaError> {
+    run(Step::Eval(expr), env)
+}
+
+/// Keep evaluating from `step` until a value comes out.
+fn run(mut step: Step, env: &mut Environment) -> Result<Expression, DracaError> {
+    let mut tail_env: Option<Environment> = None;
+    let mut in_call = false;
+    let mut in_loop = false;
+
+    loop {
+        let next = match step {
+            Step::Done(value) => {
+                if in_call {
+                    backtrace::pop();
+                }
+                return Ok(value);
+            }
+            Step::Eval(next) => eval_step(next, tail_env.as_mut().unwrap_or(&mut *env)),
+            Step::EvalIn(next, next_env) => eval_step(next, tail_env.insert(next_env)),
+            Step::Call(frame, proc, proc_env) => {
+                // A loop in tail position of an ordinary procedure still belongs to it.
+                if in_call {
+                    backtrace::replace(frame);
+                    in_loop &= proc.is_loop;
+                } else {
+                    backtrace::push(frame);
+                    in_call = true;
+                    in_loop = proc.is_loop;
+                }
+                eval_body(&proc.body, proc_env)
+            }
+        };
+
+        step = match next {
+            Ok(next) => next,
+            // `?` returns from the procedure being run.
+            Err(DracaError::Return(value)) if in_call => Step::Done(*value),
+            // A named `let` is part of the loop or block around it.
+            Err(e @ (DracaError::Break { .. } | DracaError::Continue)) if in_loop => {
+                backtrace::pop();
+                return Err(e);
+            }
+            // Loops and blocks cannot be left from inside a procedure they call.
+            Err(e @ (DracaError::Break { .. } | DracaError::Continue)) if in_call => {
+                return Err(DracaError::syntax(e.to_string()));
+            }
+            Err(e) => return Err(e),
+        };
+    }
+}
+
+fn eval_step(expr: Expression, env: &mut Environment) -> Result<Step, DracaError> {
     match expr {
         Expression::Bool(_)
         | Expression::Number(_)
         | Expression::Func(_)
         | Expression::Quoted(_) // Pass as is.
+        | Expression::Struct(_)
+        | Expression::Enum(_)
         | Expression::Nil
-        | Expression::String(_) => Ok(expr),
+        | Expression::String(_)
+        | Expression::Char(_) => Ok(Step::Done(expr)),
+        // Keywords, as in `(f :key value)`, evaluate to themselves.
+        Expression::Symbol(ref s) if s.len() > 1 && s.starts_with(':') => Ok(Step::Done(expr)),
         Expression::Symbol(s) => env
             .get(&s)
-            .cloned()
-            .ok_or_else(|| format!("Undefined symbol: {s}")),
-        Expression::List(list) => eval_list(&list, env),
-        Expression::Function(_) => Err("Unexpected function definition".into()),
+            .map(Step::Done)
+            .ok_or_else(|| DracaError::UndefinedSymbol(s.to_string()).at(s.span())),
+        Expression::List(list) => eval_list(&list, env).map_err(|e| e.at(list.span())),
+        Expression::Function(_) | Expression::Macro(_) => {
+            Err(DracaError::syntax("unexpected function definition"))
+        }
     }
 }
 
-pub(crate) fn eval_list(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+/// The names [`eval_list`] handles itself rather than looking them up, which `define` and `set!`
+/// refuse to bind, since calls to them would never reach the binding.
+const SPECIAL_FORMS: &[&str] = &[
+    "define",
+    "define/in-namespace",
+    "namespace/symbol",
+    "namespace/as-list",
+    "quote",
+    "quasiquote",
+    "unquote",
+    "unquote-splicing",
+    "eval-file",
+    "require",
+    "deconst-fn",
+    "if",
+    "cond",
+    "when",
+    "unless",
+    "and",
+    "or",
+    "let",
+    "let*",
+    "letrec",
+    "set!",
+    "while",
+    "loop",
+    "for",
+    "break",
+    "block",
+    "return",
+    "continue",
+    "match",
+    "?",
+    "try",
+    "if-let",
+    "when-let",
+    "let-else",
+    "lambda",
+    "defstruct!",
+    "defenum!",
+    "defmacro",
+    "macroexpand-1",
+    "macroexpand",
+];
+
+/// Refuse to bind `name` if it is one of the [`SPECIAL_FORMS`].
+fn check_bindable(name: &Symbol, form: &str) -> Result<(), DracaError> {
+    if SPECIAL_FORMS.contains(&name.as_str()) {
+        return Err(DracaError::syntax(format!(
+            "`{form}` cannot bind `{name}`, which is a special form"
+        ))
+        .at(name.span()));
+    }
+    Ok(())
+}
+
+fn eval_list(list: &List, env: &mut Environment) -> Result<Step, DracaError> {
     if list.is_empty() {
-        return Err("Cannot evaluate empty list".into());
+        return Err(DracaError::syntax("cannot evaluate an empty list"));
     }
 
     match &list[0] {
         Expression::Symbol(head) => {
             let args_sans_head = &list[1..];
             match head.as_str() {
-                "define" => eval_define(args_sans_head, env),
-                "define/in-namespace" => eval_define_namespace(args_sans_head, env),
-                "namespace/symbol" => eval_symbol_namespace(args_sans_head, env),
-                "namespace/as-list" => Ok(eval_symbol_namespace_as_list(env)),
-                "quote" => Ok(eval_quote(args_sans_head)),
-                "eval-file" => eval_file(args_sans_head, env),
-                "require" => eval_require(args_sans_head, env),
-                "deconst-fn" => eval_deconst_fn(args_sans_head, env),
+                "define" => eval_define(args_sans_head, env).map(Step::Done),
+                "define/in-namespace" => eval_define_namespace(args_sans_head, env).map(Step::Done),
+                "namespace/symbol" => eval_symbol_namespace(args_sans_head, env).map(Step::Done),
+                "namespace/as-list" => Ok(Step::Done(eval_symbol_namespace_as_list(env))),
+                "quote" => eval_quote(args_sans_head).map(Step::Done),
+                "quasiquote" => eval_quasiquote(args_sans_head, env).map(Step::Done),
+                "unquote" | "unquote-splicing" => Err(DracaError::syntax(format!(
+                    "`{head}` can only be used inside a quasiquote"
+                ))),
+                "eval-file" => eval_file(args_sans_head, env).map(Step::Done),
+                "require" => eval_require(args_sans_head, env).map(Step::Done),
+                "deconst-fn" => eval_deconst_fn(args_sans_head, env).map(Step::Done),
                 "if" => eval_if(args_sans_head, env),
-                "let" => eval_let(args_sans_head, env),
-                "lambda" => eval_lambda(args_sans_head, env),
-                _ => apply_function(head, args_sans_head, env),
+                "cond" => eval_cond(args_sans_head, env),
+                "when" => eval_when(args_sans_head, env, true),
+                "unless" => eval_when(args_sans_head, env, false),
+                "and" => eval_and_or(args_sans_head, env, false).map(Step::Done),
+                "or" => eval_and_or(args_sans_head, env, true).map(Step::Done),
+                "let" => eval_let(LetKind::Let, list, args_sans_head, env),
+                "let*" => eval_let(LetKind::Sequential, list, args_sans_head, env),
+                "letrec" => eval_let(LetKind::Recursive, list, args_sans_head, env),
+                "set!" => eval_set(args_sans_head, env).map(Step::Done),
+                "while" => eval_while(args_sans_head, env).map(Step::Done),
+                "loop" => eval_loop(args_sans_head, env).map(Step::Done),
+                "for" => eval_for(args_sans_head, env).map(Step::Done),
+                "break" => eval_break(args_sans_head, env),
+                "block" => eval_block(args_sans_head, env).map(Step::Done),
+                "return" => eval_return(args_sans_head, env),
+                "continue" if args_sans_head.is_empty() => Err(DracaError::Continue),
+                "continue" => Err(DracaError::arity(
+                    "continue",
+                    ArgCount::Exactly(0),
+                    args_sans_head.len(),
+                )),
+                "match" => eval_match(args_sans_head, env),
+                "?" => eval_propagate(args_sans_head, env).map(Step::Done),
+                "try" => eval_try(args_sans_head, env).map(Step::Done),
+                "if-let" => eval_if_let(args_sans_head, env),
+                "when-let" => eval_when_let(args_sans_head, env),
+                "let-else" => eval_let_else(args_sans_head, env),
+                "lambda" => eval_lambda(args_sans_head, env).map(Step::Done),
+                "defstruct!" => eval_defstruct(args_sans_head, env).map(Step::Done),
+                "defenum!" => eval_defenum(args_sans_head, env).map(Step::Done),
+                "defmacro" => eval_defmacro(args_sans_head, env).map(Step::Done),
+                "macroexpand-1" => eval_macroexpand(args_sans_head, env, false).map(Step::Done),
+                "macroexpand" => eval_macroexpand(args_sans_head, env, true).map(Step::Done),
+                _ => apply_function(head, list, env),
             }
         }
         other => {
             // Head is not a symbol; evaluate it as a function expression
             let func = eval_expr(other.clone(), env)?;
-            let args = list[1..]
-                .iter()
-                .map(|e| eval_expr(e.clone(), env))
-                .collect::<Result<Vec<_>, _>>()?;
+            let args = eval_args(&list[1..], env)?;
 
             match func {
-                Expression::Func(f) => f(&args),
-                Expression::Function(proc) => {
-                    let mut local_env = proc.env.clone();
-
-                    // Bind parameters
-                    for (param, arg) in proc.params.iter().zip(args) {
-                        let Expression::Symbol(p) = param else {
-                            return Err("Invalid parameter name in lambda".into());
-                        };
-                        local_env.insert(NamespaceItem::from_str(p), arg);
-                    }
-
-                    // Evaluate body
-                    let mut result = Expression::Bool(false);
-                    for expr in &proc.body {
-                        result = eval_expr(expr.clone(), &mut local_env)?;
-                    }
-                    Ok(result)
-                }
-                _ => Err("Head of list is not a function".into()),
+                Expression::Func(f) => f(&args).map(Step::Done),
+                Expression::Function(proc) => apply_procedure(proc, args, None, list),
+                other => Err(DracaError::NotCallable(other.to_string())),
             }
         }
     }
 }
 
-fn apply_function(
-    name: &str,
-    args: &[Expression],
-    env: &mut Environment,
-) -> Result<Expression, String> {
-    let Some(exp) = &env.get(name).cloned() else {
-        return Err(format!("Undefined function: {name}"));
+fn eval_args(args: &[Expression], env: &mut Environment) -> Result<Vec<Expression>, DracaError> {
+    args.iter().map(|e| eval_expr(e.clone(), env)).collect()
+}
+
+/// Call the function bound to `name` with the rest of `call` as its arguments.
+fn apply_function(name: &Symbol, call: &List, env: &mut Environment) -> Result<Step, DracaError> {
+    let Some(exp) = env.get(name) else {
+        return Err(DracaError::UndefinedSymbol(name.to_string()).at(name.span()));
     };
 
     match exp {
-        Expression::Func(func) => {
-            let args = args
-                .iter()
-                .map(|e| eval_expr(e.clone(), env))
-                .collect::<Result<Vec<_>, _>>()?;
-
-            func(&args)
+        Expression::Func(func) => func(&eval_args(&call[1..], env)?).map(Step::Done),
+        Expression::Function(proc) => {
+            let args = eval_args(&call[1..], env)?;
+            apply_procedure(proc, args, Some(name), call)
         }
+        Expression::Macro(proc) => expand_macro(proc, name, call, env).map(Step::Eval),
+        _ => Err(DracaError::NotCallable(name.to_string())),
+    }
+}
 
-        Expression::Function(proc) => {
-            let args = args
-                .iter()
-                .map(|e| eval_expr(e.clone(), env))
-                .collect::<Result<Vec<_>, _>>()?;
+/// Bind `args` to the parameters of `proc`, handing its body back to [`eval_expr`] to run.
+///
+/// `called_as` is the name used at the call site, for procedures that were never defined under
+/// a name of their own.
+fn apply_procedure(
+    proc: Rc<Procedure>,
+    args: Vec<Expression>,
+    called_as: Option<&Symbol>,
+    call: &List,
+) -> Result<Step, DracaError> {
+    let mut local_env = proc.env.nested();
 
-            let mut local_env = proc.env.clone();
-            local_env.insert(NamespaceItem::from_str(name), exp.clone());
+    // A named `let` binds itself in each call rather than in the environment it closes over, so
+    // the two do not keep each other alive.
+    if proc.is_loop
+        && let Some(name) = &proc.name
+    {
+        local_env.insert(name, Expression::Function(Rc::clone(&proc)));
+    }
 
-            for (param, arg) in proc.params.iter().zip(args) {
-                let Expression::Symbol(p) = param else {
-                    return Err("Invalid parameter name".into());
-                };
-                local_env.insert(NamespaceItem::from_str(p), arg);
-            }
+    let frame = CallFrame {
+        name: match (&proc.name, called_as) {
+            (Some(name), _) => Rc::clone(name),
+            (None, Some(name)) => name.as_str().into(),
+            (None, None) => "<lambda>".into(),
+        },
+        args: args.len(),
+        span: call.span(),
+    };
 
-            let mut result = Expression::Bool(false);
-            for expr in &proc.body {
-                result = eval_expr(expr.clone(), &mut local_env)?;
-            }
+    bind_params(&proc.params, args, &frame.name, &mut local_env)?;
+
+    Ok(Step::Call(frame, proc, local_env))
+}
+
+/// Bind `args` to `params` in `env`, evaluating the defaults of missing `&optional` and `&key`
+/// parameters there too, so they can refer to the parameters before them.
+fn bind_params(
+    params: &Params,
+    args: Vec<Expression>,
+    name: &str,
+    env: &mut Environment,
+) -> Result<(), DracaError> {
+    let arg_count = params.arg_count();
+    let positional = params.required.len() + params.optional.len();
+    let too_many = params.rest.is_none() && params.key.is_empty() && args.len() > positional;
+
+    if args.len() < params.required.len() || too_many {
+        return Err(DracaError::arity(name, arg_count, args.len()));
+    }
+
+    let mut args = args.into_iter();
+
+    for param in &params.required {
+        env.insert(param, args.next().expect("checked against the arity"));
+    }
+
+    for (param, default) in &params.optional {
+        let value = match args.next() {
+            Some(arg) => arg,
+            None => eval_expr(default.clone(), env)?,
+        };
+        env.insert(param, value);
+    }
+
+    let rest = args.collect::<Vec<_>>();
+
+    if !params.key.is_empty() {
+        if rest.len() % 2 != 0 {
+            return Err(DracaError::syntax(format!(
+                "`{name}` takes keyword arguments in `:key value` pairs"
+            )));
+        }
+
+        let mut given = Vec::with_capacity(rest.len() / 2);
+        for pair in rest.chunks(2) {
+            let key = match &pair[0] {
+                Expression::Symbol(key)
+                    if let Some(key) = key.strip_prefix(':')
+                        && params.key.iter().any(|(param, _)| param == key) =>
+                {
+                    key
+                }
+                other => {
+                    return Err(DracaError::syntax(format!(
+                        "`{name}` has no keyword parameter `{other}`"
+                    )));
+                }
+            };
+            given.push((key.to_string(), pair[1].clone()));
+        }
 
-            Ok(result)
+        for (param, default) in &params.key {
+            let value = match given.iter().rfind(|(key, _)| param == key.as_str()) {
+                Some((_, value)) => value.clone(),
+                None => eval_expr(default.clone(), env)?,
+            };
+            env.insert(param, value);
         }
+    }
 
-        _ => Err(format!("Undefined function: {name}")),
+    if let Some(param) = &params.rest {
+        env.insert(param, Expression::list(rest));
     }
+
+    Ok(())
 }
 
-fn eval_define(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
-    match list {
+/// Run the macro `proc` on the unevaluated arguments of `call`, handing back the form it expands
+/// to.
+fn expand_macro(
+    proc: Rc<Procedure>,
+    name: &Symbol,
+    call: &List,
+    env: &mut Environment,
+) -> Result<Expression, DracaError> {
+    let step = apply_procedure(proc, call[1..].to_vec(), Some(name), call)?;
+
+    Ok(match run(step, env)? {
+        // Point errors in the expansion back at the macro call.
+        Expression::List(expansion) => Expression::List(expansion.or_span(call.span())),
+        expansion => expansion,
+    })
+}
+
+/// Evaluate a body of forms in `env`, leaving the last one in tail position.
+fn eval_body(body: &[Expression], mut env: Environment) -> Result<Step, DracaError> {
+    let Some((last, init)) = body.split_last() else {
+        return Ok(Step::Done(Expression::Bool(false)));
+    };
+
+    for expr in init {
+        eval_expr(expr.clone(), &mut env)?;
+    }
+
+    Ok(Step::EvalIn(last.clone(), env))
+}
+
+fn eval_define(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let (name, expr, mutable) = match list {
+        // (define mut name expr)
+        [Expression::Symbol(m), Expression::Symbol(name), expr] if m == "mut" => (name, expr, true),
         // (define name expr)
-        [Expression::Symbol(name), expr] => {
-            let value = eval_expr(expr.clone(), env)?;
-            env.insert(NamespaceItem::from_str(name), value);
-            Ok(Expression::Symbol(name.clone()))
-        }
+        [Expression::Symbol(name), expr] => (name, expr, false),
         // (define (f args...) body...)
         [Expression::List(func), value @ ..] => {
             let Some(Expression::Symbol(name)) = func.first() else {
-                return Err("Invalid define syntax".into());
+                return Err(DracaError::syntax("`define` needs a function name"));
             };
 
-            let lambda = Expression::List({
+            let lambda = Expression::list({
                 let mut v = Vec::with_capacity(list.len());
                 v.push(Expression::symbol("lambda"));
-                v.push(Expression::List(func[1..].to_vec()));
+                v.push(Expression::list(func[1..].to_vec()));
                 v.extend_from_slice(value);
                 v
             });
 
-            eval_define(&[Expression::Symbol(name.clone()), lambda], env)
+            return eval_define(&[Expression::Symbol(name.clone()), lambda], env);
+        }
+        _ => {
+            return Err(DracaError::arity(
+                "define",
+                ArgCount::AtLeast(2),
+                list.len(),
+            ));
         }
-        _ => Err("`define` requires at least two arguments".into()),
+    };
+
+    check_bindable(name, "define")?;
+    let mut value = eval_expr(expr.clone(), env)?;
+
+    if let Expression::Function(proc) = &mut value
+        && proc.name.is_none()
+    {
+        Rc::make_mut(proc).name = Some(name.as_str().into());
+    }
+
+    if mutable {
+        env.insert_mut(name, value);
+    } else {
+        env.insert(name, value);
     }
+
+    Ok(Expression::Symbol(name.clone()))
 }
 
-fn eval_define_namespace(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+fn eval_define_namespace(
+    list: &[Expression],
+    env: &mut Environment,
+) -> Result<Expression, DracaError> {
     if list.len() < 2 {
-        return Err("define/in-namespace requires a namespace and a form".into());
+        return Err(DracaError::arity(
+            "define/in-namespace",
+            ArgCount::Exactly(2),
+            list.len(),
+        ));
     }
 
     // (foo::bar::baz FORM)
     let Expression::Symbol(ns_name) = &list[0] else {
-        return Err("Expected namespace symbol".into());
+        return Err(DracaError::type_mismatch(
+            "namespace symbol",
+            list[0].type_name(),
+        ));
     };
 
-    let namespace = NamespaceItem::from(ns_name.as_str());
-    let mut inner_env = env.clone().with_scope(namespace.frags());
+    let mut inner_env = env.clone().with_scope(Namespace::from_str(ns_name));
     let rhs = &list[1];
 
-    // Must be a define form
+    // Must be a define or defmacro form
     let Expression::List(items) = rhs else {
-        return Err("Expected define form inside define/in-namespace".into());
+        return Err(DracaError::syntax(
+            "expected a `define` or `defmacro` form inside `define/in-namespace`",
+        ));
     };
 
-    if !matches!(items.first(), Some(Expression::Symbol(s)) if s == "define") {
-        return Err("Expected define form inside define/in-namespace".into());
-    }
+    let definer: fn(&[Expression], &mut Environment) -> Result<Expression, DracaError> =
+        match items.first() {
+            Some(Expression::Symbol(s)) if s == "define" => eval_define,
+            Some(Expression::Symbol(s)) if s == "defmacro" => eval_defmacro,
+            _ => {
+                return Err(DracaError::syntax(
+                    "expected a `define` or `defmacro` form inside `define/in-namespace`",
+                ));
+            }
+        };
+
+    // Skip the `define` or `defmacro` itself.
+    let mut rewritten = items[1..].to_vec();
+
+    // The name comes after `mut` in `(define mut name expr)`.
+    let name_idx = match &rewritten[..] {
+        [Expression::Symbol(m), Expression::Symbol(_), _] if m == "mut" => 1,
+        _ => 0,
+    };
 
-    match items.get(1) {
+    let full_name = match rewritten.get_mut(name_idx) {
         // (define (name args...) ...)
         Some(Expression::List(func_head)) => {
             let Some(Expression::Symbol(inner_name)) = func_head.first() else {
-                return Err("Invalid inner define syntax".into());
+                return Err(DracaError::syntax("`define` needs a function name"));
             };
 
             let full_name = format!("{ns_name}::{inner_name}");
-            let full_sym = NamespaceItem::from(full_name.as_str());
-
-            // Rewrite function head
-            // Remember that this includes `define` in it, so we skip the first element which we
-            // know is `define`.
-            let mut rewritten = items.iter().skip(1).cloned().collect::<Vec<_>>();
-            if let Expression::List(head) = &mut rewritten[0] {
-                head[0] = Expression::Symbol(full_name.clone());
-            }
-
-            eval_define(&rewritten, &mut inner_env)?;
-
-            let Some(bound) = inner_env.get(&full_name) else {
-                return Err(format!("Inner define made no binding for {full_name}"));
-            };
-
-            env.insert(full_sym, bound.clone());
-            Ok(Expression::Symbol(full_name))
+            func_head[0] = Expression::symbol(&full_name);
+            full_name
         }
 
-        // (define name expr)
-        Some(Expression::Symbol(inner_name)) => {
-            let full_name = format!("{ns_name}::{inner_name}");
-            let full_sym = NamespaceItem::from(full_name.as_str());
-
-            let mut rewritten = items.clone();
-            rewritten[1] = Expression::Symbol(full_name.clone());
-
-            eval_define(&rewritten, &mut inner_env)?;
+        // (define name expr) or (defmacro name (params...) body...)
+        Some(name @ Expression::Symbol(_)) => {
+            let full_name = format!("{ns_name}::{name}");
+            *name = Expression::symbol(&full_name);
+            full_name
+        }
 
-            let Some(bound) = inner_env.get(&full_name).cloned() else {
-                return Err(format!("Inner define made no binding for {full_name}"));
-            };
+        _ => return Err(DracaError::syntax("`define` needs a name")),
+    };
 
-            env.insert(full_sym, bound);
-            Ok(Expression::Symbol(full_name))
-        }
+    // `inner_env` shares its bindings with `env`, so this defines it there too.
+    definer(&rewritten, &mut inner_env)?;
 
-        _ => Err("Invalid inner define syntax".into()),
-    }
+    Ok(Expression::symbol(full_name))
 }
 
-fn eval_symbol_namespace(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
-    let expr = &list[0];
+fn eval_symbol_namespace(
+    list: &[Expression],
+    env: &mut Environment,
+) -> Result<Expression, DracaError> {
+    let Some(expr) = list.first() else {
+        return Err(DracaError::arity(
+            "namespace/symbol",
+            ArgCount::Exactly(1),
+            0,
+        ));
+    };
 
     let sym = match expr {
         Expression::Symbol(s) => Some(s.clone()),
@@ -246,58 +558,129 @@ fn eval_symbol_namespace(list: &[Expression], env: &mut Environment) -> Result<E
 
     Ok(sym
         .and_then(|s| env.get_namespace_str(&s))
-        .map_or(Expression::Bool(false), Expression::Symbol))
+        .map_or(Expression::Bool(false), Expression::symbol))
 }
 
 fn eval_symbol_namespace_as_list(env: &mut Environment) -> Expression {
-    Expression::List(
+    Expression::list(
         env.scopes()
             .iter()
-            .map(|n| Expression::Symbol(n.to_string()))
-            .collect(),
+            .map(|n| Expression::symbol(n.to_string()))
+            .collect::<Vec<_>>(),
     )
 }
 
-fn eval_quote(list: &[Expression]) -> Expression {
-    list[0].clone()
+fn eval_quote(list: &[Expression]) -> Result<Expression, DracaError> {
+    list.first()
+        .cloned()
+        .ok_or_else(|| DracaError::arity("quote", ArgCount::Exactly(1), 0))
+}
+
+fn eval_quasiquote(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    match list {
+        [template] => fill_template(template, 1, env),
+        _ => Err(DracaError::arity(
+            "quasiquote",
+            ArgCount::Exactly(1),
+            list.len(),
+        )),
+    }
+}
+
+/// If `expr` is `(name x)`, hand back `x`.
+fn unwrap_form<'e>(expr: &'e Expression, name: &str) -> Option<&'e Expression> {
+    match expr {
+        Expression::List(list) => match &list[..] {
+            [Expression::Symbol(head), inner] if head == name => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Copy a quasiquoted `template`, evaluating whatever is unquoted at `depth` 1.
+///
+/// Every nested quasiquote goes one level deeper and every unquote comes one level back out, so
+/// only the unquotes that belong to the outermost quasiquote are evaluated.
+fn fill_template(
+    template: &Expression,
+    depth: usize,
+    env: &mut Environment,
+) -> Result<Expression, DracaError> {
+    let rewrap =
+        |name: &str, inner: Expression| Expression::list([Expression::symbol(name), inner]);
+
+    if let Some(inner) = unwrap_form(template, "unquote") {
+        return if depth == 1 {
+            eval_expr(inner.clone(), env)
+        } else {
+            Ok(rewrap("unquote", fill_template(inner, depth - 1, env)?))
+        };
+    }
+
+    if let Some(inner) = unwrap_form(template, "quasiquote") {
+        return Ok(rewrap("quasiquote", fill_template(inner, depth + 1, env)?));
+    }
+
+    match template {
+        Expression::List(list) => {
+            let mut filled = Vec::with_capacity(list.len());
+
+            for item in list {
+                match unwrap_form(item, "unquote-splicing") {
+                    Some(inner) if depth == 1 => match eval_expr(inner.clone(), env)? {
+                        Expression::Nil => {}
+                        spliced => filled.extend(crate::core::list::extract_list(&spliced)?),
+                    },
+                    Some(inner) => filled.push(rewrap(
+                        "unquote-splicing",
+                        fill_template(inner, depth - 1, env)?,
+                    )),
+                    None => filled.push(fill_template(item, depth, env)?),
+                }
+            }
+
+            Ok(Expression::List(List::from(filled).or_span(list.span())))
+        }
+        Expression::Quoted(quoted) => Ok(Expression::Quoted(Box::new(fill_template(
+            quoted, depth, env,
+        )?))),
+        other => Ok(other.clone()),
+    }
 }
 
-fn eval_require(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+fn eval_require(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
     match list {
         [Expression::Symbol(sym)] => {
             env.add_scope(Namespace::from_str(sym));
             Ok(Expression::Bool(true))
         }
-        _ => Err("`require` requires at least 1 symbol argument".into()),
+        _ => Err(DracaError::syntax(
+            "`require` takes a single namespace symbol",
+        )),
     }
 }
 
-fn eval_deconst_fn(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+fn eval_deconst_fn(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
     match list {
         [sym @ Expression::Symbol(name)] => match env.get(name) {
             Some(v) => {
                 println!("{v}");
                 Ok(Expression::Quoted(Box::new(sym.clone())))
             }
-            None => Err("Could not find symbol".into()),
+            None => Err(DracaError::UndefinedSymbol(name.to_string())),
         },
-        _ => Err("`deconst-fn` requires a symbol".into()),
+        _ => Err(DracaError::syntax("`deconst-fn` takes a single symbol")),
     }
 }
 
-fn eval_file(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+fn eval_file(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
     let [Expression::Symbol(path)] = list else {
-        return Err("`eval-file` requires a symbol".into());
+        return Err(DracaError::syntax("`eval-file` takes a single path symbol"));
     };
 
-    let contents: Cow<'_, str> = match fs::read_to_string(path) {
-        Ok(o) => Cow::Owned(o),
-        Err(_) => Cow::Borrowed("()"),
-    };
-    let parsed = crate::parser::parse(&contents).map_err(|e| {
-        eprintln!("{e:?}");
-        String::from("Parsing failed")
-    })?;
+    let contents = fs::read_to_string(path.as_str())?;
+    let parsed = crate::parser::parse_source(path, &contents)?;
 
     let mut result = Expression::Bool(true);
     for expr in parsed {
@@ -307,65 +690,2043 @@ fn eval_file(list: &[Expression], env: &mut Environment) -> Result<Expression, S
     Ok(result)
 }
 
-fn eval_if(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+/// Evaluate a condition, which must come out as a bool.
+fn eval_condition(cond: &Expression, env: &mut Environment) -> Result<bool, DracaError> {
+    match eval_expr(cond.clone(), env)? {
+        Expression::Bool(b) => Ok(b),
+        other => Err(DracaError::type_mismatch("bool", other.type_name())),
+    }
+}
+
+fn eval_if(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
     match list {
-        [cond, then_, else_] => match eval_expr(cond.clone(), env)? {
-            Expression::Bool(true) => eval_expr(then_.clone(), env),
-            Expression::Bool(false) => eval_expr(else_.clone(), env),
-            _ => Err("Invalid condition in if expression".into()),
+        [cond, then_, else_] => Ok(Step::Eval(if eval_condition(cond, env)? {
+            then_.clone()
+        } else {
+            else_.clone()
+        })),
+        _ => Err(DracaError::arity("if", ArgCount::Exactly(3), list.len())),
+    }
+}
+
+/// `(cond (test body...) ... (else body...))` runs the body of the first clause whose test holds,
+/// or gives `nil` if none do.
+fn eval_cond(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    for clause in list {
+        let Expression::List(clause) = clause else {
+            return Err(DracaError::syntax(
+                "`cond` clauses must look like `(test body...)`",
+            ));
+        };
+
+        let Some((test, body)) = clause.split_first() else {
+            return Err(DracaError::syntax("`cond` clauses need a test"));
+        };
+
+        let holds = match test {
+            Expression::Symbol(s) if s == "else" => true,
+            test => eval_condition(test, env)?,
+        };
+
+        if holds {
+            return if body.is_empty() {
+                Ok(Step::Done(Expression::Bool(true)))
+            } else {
+                eval_body(body, env.clone())
+            };
+        }
+    }
+
+    Ok(Step::Done(Expression::Nil))
+}
+
+/// `(when test body...)`, or `(unless test body...)` when `expected` is false, runs `body` if
+/// `test` comes out as `expected` and gives `nil` otherwise.
+fn eval_when(
+    list: &[Expression],
+    env: &mut Environment,
+    expected: bool,
+) -> Result<Step, DracaError> {
+    let Some((test, body)) = list.split_first() else {
+        let name = if expected { "when" } else { "unless" };
+        return Err(DracaError::arity(name, ArgCount::AtLeast(1), 0));
+    };
+
+    if eval_condition(test, env)? == expected {
+        eval_body(body, env.clone())
+    } else {
+        Ok(Step::Done(Expression::Nil))
+    }
+}
+
+/// `(and tests...)`, or `(or tests...)` when `short_circuit` is true, stops at the first test that
+/// comes out as `short_circuit`.
+fn eval_and_or(
+    list: &[Expression],
+    env: &mut Environment,
+    short_circuit: bool,
+) -> Result<Expression, DracaError> {
+    for test in list {
+        if eval_condition(test, env)? == short_circuit {
+            return Ok(Expression::Bool(short_circuit));
+        }
+    }
+
+    Ok(Expression::Bool(!short_circuit))
+}
+
+/// Run the body of a loop once, in a scope of its own nested in `env`.
+///
+/// Gives back the value to leave the loop with if the body used `break`.
+fn eval_loop_body(
+    body: &[Expression],
+    env: &Environment,
+) -> Result<Option<Expression>, DracaError> {
+    let mut body_env = env.nested();
+
+    for expr in body {
+        match eval_expr(expr.clone(), &mut body_env) {
+            Ok(_) => {}
+            Err(DracaError::Break { label: None, value }) => return Ok(Some(*value)),
+            Err(DracaError::Continue) => return Ok(None),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(None)
+}
+
+/// `(while test body...)`
+fn eval_while(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let Some((test, body)) = list.split_first() else {
+        return Err(DracaError::arity("while", ArgCount::AtLeast(1), 0));
+    };
+
+    while eval_condition(test, env)? {
+        if let Some(value) = eval_loop_body(body, env)? {
+            return Ok(value);
+        }
+    }
+
+    Ok(Expression::Nil)
+}
+
+/// `(loop body...)` runs until it hits a `break`, and `(loop for x in items do body...)` is
+/// another way of writing `for`.
+fn eval_loop(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    if let [Expression::Symbol(head), rest @ ..] = list
+        && head == "for"
+    {
+        return eval_for(rest, env);
+    }
+
+    loop {
+        if let Some(value) = eval_loop_body(list, env)? {
+            return Ok(value);
+        }
+    }
+}
+
+/// `(for pattern in items do body...)`, where `items` is a list or string and `do` is optional.
+fn eval_for(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let (pattern, items, body) = match list {
+        [
+            pattern,
+            Expression::Symbol(in_),
+            items,
+            Expression::Symbol(do_),
+            body @ ..,
+        ] if in_ == "in" && do_ == "do" => (pattern, items, body),
+        [pattern, Expression::Symbol(in_), items, body @ ..] if in_ == "in" => {
+            (pattern, items, body)
+        }
+        _ => {
+            return Err(DracaError::syntax(
+                "`for` must look like `(for x in items do body...)`",
+            ));
+        }
+    };
+
+    let items = match eval_expr(items.clone(), env)? {
+        Expression::String(string) => string.chars().map(Expression::Char).collect(),
+        Expression::Nil => Vec::new(),
+        Expression::Quoted(box Expression::Nil) => Vec::new(),
+        items => core::list::extract_list(&items)?,
+    };
+
+    for item in items {
+        let Some(item_env) = bind_pattern(pattern, &item, env)? else {
+            return Err(DracaError::NoMatch(item.to_string()));
+        };
+
+        if let Some(value) = eval_loop_body(body, &item_env)? {
+            return Ok(value);
+        }
+    }
+
+    Ok(Expression::Nil)
+}
+
+/// The name in a block label, like `outer` in `'outer` or `(quote outer)`.
+fn block_label(label: &Expression) -> Option<&Symbol> {
+    match label {
+        Expression::Quoted(box Expression::Symbol(label)) => Some(label),
+        _ => match unwrap_form(label, "quote") {
+            Some(Expression::Symbol(label)) => Some(label),
+            _ => None,
         },
-        _ => Err("`if` requires three arguments".into()),
     }
 }
 
-fn eval_let(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
+/// `(block 'label body...)`: run `body`, unless `(break 'label value)` leaves it early with
+/// `value`.
+fn eval_block(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let Some((label, body)) = list.split_first() else {
+        return Err(DracaError::arity("block", ArgCount::AtLeast(1), 0));
+    };
+    let Some(label) = block_label(label) else {
+        return Err(DracaError::syntax(
+            "`block` needs a label, like `(block 'outer ...)`",
+        ));
+    };
+
+    let mut block_env = env.nested();
+    let mut value = Expression::Nil;
+
+    for expr in body {
+        value = match eval_expr(expr.clone(), &mut block_env) {
+            Ok(value) => value,
+            Err(DracaError::Break {
+                label: Some(target),
+                value,
+            }) if target == label.as_str() => return Ok(*value),
+            Err(e) => return Err(e),
+        };
+    }
+
+    Ok(value)
+}
+
+/// `(break)`, `(break value)`, `(break 'label)` or `(break 'label value)`
+///
+/// A quoted symbol in first place is always taken as a label, so to leave a loop with a symbol,
+/// put it in a variable first.
+fn eval_break(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    let (label, value) = match list {
+        [] => (None, None),
+        [label] if let Some(label) = block_label(label) => (Some(label), None),
+        [label, value] if let Some(label) = block_label(label) => (Some(label), Some(value)),
+        [value] => (None, Some(value)),
+        _ => {
+            return Err(DracaError::arity(
+                "break",
+                ArgCount::Between(0, 2),
+                list.len(),
+            ));
+        }
+    };
+
+    let value = match value {
+        Some(value) => eval_expr(value.clone(), env)?,
+        None => Expression::Nil,
+    };
+
+    Err(DracaError::Break {
+        label: label.map(ToString::to_string),
+        value: Box::new(value),
+    })
+}
+
+/// `(return)` or `(return value)`
+fn eval_return(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    let value = match list {
+        [] => Expression::Nil,
+        [value] => eval_expr(value.clone(), env)?,
+        _ => {
+            return Err(DracaError::arity(
+                "return",
+                ArgCount::Between(0, 1),
+                list.len(),
+            ));
+        }
+    };
+
+    Err(DracaError::Return(Box::new(value)))
+}
+
+/// Which flavour of `let` is being evaluated.
+#[derive(Clone, Copy, PartialEq)]
+enum LetKind {
+    /// Every value is evaluated in the enclosing scope.
+    Let,
+    /// Each value can see the bindings before it.
+    Sequential,
+    /// Each value can see every binding, so local functions can call each other.
+    Recursive,
+}
+
+impl LetKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Let => "let",
+            Self::Sequential => "let*",
+            Self::Recursive => "letrec",
+        }
+    }
+}
+
+/// Split `let` bindings into `(mutable, name, value)`.
+fn let_bindings(
+    kind: LetKind,
+    bindings: &[Expression],
+) -> Result<Vec<(bool, &Symbol, &Expression)>, DracaError> {
+    bindings
+        .iter()
+        .map(|binding| match binding {
+            Expression::List(pair) => match &pair[..] {
+                [Expression::Symbol(name), value] => Ok((false, name, value)),
+                [Expression::Symbol(m), Expression::Symbol(name), value] if m == "mut" => {
+                    Ok((true, name, value))
+                }
+                _ => Err(binding),
+            },
+            _ => Err(binding),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|_| {
+            DracaError::syntax(format!(
+                "`{}` bindings must look like `(symbol value)` or `(mut symbol value)`",
+                kind.name()
+            ))
+        })
+}
+
+fn eval_let(
+    kind: LetKind,
+    call: &List,
+    list: &[Expression],
+    env: &mut Environment,
+) -> Result<Step, DracaError> {
     match list {
+        // (let name ((var init)...) body...)
+        [
+            Expression::Symbol(name),
+            Expression::List(bindings),
+            body @ ..,
+        ] if kind == LetKind::Let => eval_named_let(name, bindings, body, call, env),
         [Expression::List(bindings), rest @ ..] => {
-            let mut local_env = env.clone();
-
-            for binding in bindings {
-                match binding {
-                    Expression::List(pair) => match &pair[..] {
-                        [Expression::Symbol(name), value] => {
-                            let val = eval_expr(value.clone(), env)?;
-                            local_env.insert(NamespaceItem::from_str(name), val);
-                        }
-                        _ => return Err("Invalid `let` binding. Must be (`symbol` `value`)".into()),
-                    },
-                    _ => return Err("Invalid `let` binding. Must be `(list)`".into()),
+            let bindings = let_bindings(kind, bindings)?;
+            let mut local_env = match kind {
+                LetKind::Recursive => env.recursive(),
+                _ => env.nested(),
+            };
+
+            if kind == LetKind::Recursive {
+                for (_, name, _) in &bindings {
+                    local_env.insert(name, Expression::Nil);
+                }
+            }
+
+            for (mutable, name, value) in bindings {
+                let val = match kind {
+                    LetKind::Let => eval_expr(value.clone(), env)?,
+                    LetKind::Sequential | LetKind::Recursive => {
+                        eval_expr(value.clone(), &mut local_env)?
+                    }
+                };
+
+                if mutable {
+                    local_env.insert_mut(name, val);
+                } else {
+                    local_env.insert(name, val);
                 }
             }
 
-            let mut result = Expression::Bool(false);
-            for expr in rest {
-                result = eval_expr(expr.clone(), &mut local_env)?;
+            if kind == LetKind::Recursive {
+                // Run the body in a scope of its own, so `local_env` can be released when it ends.
+                return eval_body(rest, local_env.nested());
             }
 
-            Ok(result)
+            eval_body(rest, local_env)
         }
-        _ => Err("`let` bindings must be a list".into()),
+        _ => Err(DracaError::syntax(format!(
+            "`{}` bindings must be a list",
+            kind.name()
+        ))),
     }
 }
 
-fn eval_lambda(list: &[Expression], env: &mut Environment) -> Result<Expression, String> {
-    match list {
-        [params, body @ ..] => {
-            let params = match params {
-                Expression::List(p) => p.clone(),
-                _ => return Err("`lambda` parameter list must be a list".into()),
-            };
-            for param in &params {
-                if !matches!(param, Expression::Symbol(_)) {
-                    return Err("`lambda` parameters must be symbols".into());
-                }
-            }
+/// `(let name ((var init)...) body...)`: define a local procedure `name` taking `var...`, then
+/// call it with `init...`. Calling `name` in tail position from `body` loops.
+///
+/// Special forms are looked up before bindings, so `name` cannot be one of them, such as `loop`.
+fn eval_named_let(
+    name: &Symbol,
+    bindings: &[Expression],
+    body: &[Expression],
+    call: &List,
+    env: &mut Environment,
+) -> Result<Step, DracaError> {
+    check_bindable(name, "let")?;
 
-            Ok(Expression::Function(Procedure {
-                params,
-                body: body.to_vec(),
-                env: env.clone(),
-            }))
+    let mut params = Vec::with_capacity(bindings.len());
+    let mut args = Vec::with_capacity(bindings.len());
+
+    for (mutable, param, value) in let_bindings(LetKind::Let, bindings)? {
+        if mutable {
+            return Err(DracaError::syntax(
+                "the bindings of a named `let` cannot be `mut`",
+            ));
         }
-        _ => Err("`lambda` requires parameters and a body".into()),
+        params.push(param.clone());
+        args.push(eval_expr(value.clone(), env)?);
+    }
+
+    let proc = Rc::new(Procedure {
+        name: Some(name.as_str().into()),
+        params: Params::required(params),
+        body: body.to_vec(),
+        env: env.clone(),
+        is_loop: true,
+    });
+
+    apply_procedure(proc, args, None, call)
+}
+
+/// `(? expr)`: the value inside `Some` or `Ok`, or return `None` or `Err` from the enclosing
+/// procedure.
+fn eval_propagate(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let [expr] = list else {
+        return Err(DracaError::arity("?", ArgCount::Exactly(1), list.len()));
+    };
+
+    match eval_expr(expr.clone(), env)? {
+        Expression::Enum(en) if en.is_option() || en.is_result() => {
+            match (en.variant_name(), &en.values[..]) {
+                ("Some" | "Ok", [value]) => Ok(value.clone()),
+                _ => Err(DracaError::Return(Box::new(Expression::Enum(en)))),
+            }
+        }
+        other => Err(DracaError::type_mismatch(
+            "option or result",
+            other.type_name(),
+        )),
+    }
+}
+
+/// `(try body... (catch e handler...) (finally cleanup...))`, where both clauses are optional.
+///
+/// If `body` raises an error, the handler runs with `e` bound to it as an `error` struct. The
+/// cleanup runs however the rest finished, but its value is thrown away.
+fn eval_try(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let clause = |expr: Option<&Expression>, name: &str| match expr {
+        Some(Expression::List(clause)) => match clause.split_first() {
+            Some((Expression::Symbol(head), rest)) if head == name => Some(rest.to_vec()),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let mut body = list;
+
+    let finally = clause(body.last(), "finally");
+    if finally.is_some() {
+        body = &body[..body.len() - 1];
+    }
+
+    let catch = clause(body.last(), "catch");
+    if catch.is_some() {
+        body = &body[..body.len() - 1];
+    }
+
+    let depth = backtrace::depth();
+
+    let result = body
+        .iter()
+        .try_fold(Expression::Nil, |_, expr| eval_expr(expr.clone(), env));
+
+    let result = match (result, catch) {
+        (Err(e), Some(catch)) if !e.is_control_flow() => {
+            let Some((Expression::Symbol(name), handler)) = catch.split_first() else {
+                return Err(DracaError::syntax(
+                    "`catch` must look like `(catch name handler...)`",
+                ));
+            };
+
+            // The calls that raised the error will never return now.
+            backtrace::unwind_to(depth);
+
+            let mut handler_env = env.nested();
+            handler_env.insert(name, e.to_value());
+
+            handler.iter().try_fold(Expression::Nil, |_, expr| {
+                eval_expr(expr.clone(), &mut handler_env)
+            })
+        }
+        (result, _) => result,
+    };
+
+    for expr in finally.unwrap_or_default() {
+        eval_expr(expr, env)?;
+    }
+
+    result
+}
+
+/// Match `value` against `pattern`, handing back a new lexical scope holding whatever it bound.
+fn bind_pattern(
+    pattern: &Expression,
+    value: &Expression,
+    env: &Environment,
+) -> Result<Option<Environment>, DracaError> {
+    let mut bindings = Vec::new();
+    if !pattern::matches(pattern, value, &mut bindings)? {
+        return Ok(None);
+    }
+
+    let mut local_env = env.nested();
+    for (name, val) in bindings {
+        local_env.insert(&name, val);
+    }
+
+    Ok(Some(local_env))
+}
+
+fn eval_if_let(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    let [pattern, value, then_, else_] = list else {
+        return Err(DracaError::arity(
+            "if-let",
+            ArgCount::Exactly(4),
+            list.len(),
+        ));
+    };
+
+    let value = eval_expr(value.clone(), env)?;
+
+    match bind_pattern(pattern, &value, env)? {
+        Some(local_env) => Ok(Step::EvalIn(then_.clone(), local_env)),
+        None => Ok(Step::Eval(else_.clone())),
+    }
+}
+
+fn eval_when_let(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    let [pattern, value, body @ ..] = list else {
+        return Err(DracaError::arity(
+            "when-let",
+            ArgCount::AtLeast(2),
+            list.len(),
+        ));
+    };
+
+    let value = eval_expr(value.clone(), env)?;
+
+    match bind_pattern(pattern, &value, env)? {
+        Some(local_env) => eval_body(body, local_env),
+        None => Ok(Step::Done(Expression::Nil)),
+    }
+}
+
+/// `(let-else pattern value else body...)`: run `body` with the bindings of `pattern`, or run
+/// `else`, which must not return, like Rust's `let ... else`.
+fn eval_let_else(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    let [pattern, value, else_, body @ ..] = list else {
+        return Err(DracaError::arity(
+            "let-else",
+            ArgCount::AtLeast(3),
+            list.len(),
+        ));
+    };
+
+    let value = eval_expr(value.clone(), env)?;
+
+    match bind_pattern(pattern, &value, env)? {
+        Some(local_env) => eval_body(body, local_env),
+        None => {
+            eval_expr(else_.clone(), env)?;
+            Err(DracaError::syntax(format!(
+                "the `else` branch of `let-else` must not return, but `{value}` did not match"
+            )))
+        }
+    }
+}
+
+fn eval_match(list: &[Expression], env: &mut Environment) -> Result<Step, DracaError> {
+    let [scrutinee, arms @ ..] = list else {
+        return Err(DracaError::arity("match", ArgCount::AtLeast(1), 0));
+    };
+
+    let value = eval_expr(scrutinee.clone(), env)?;
+
+    for arm in arms {
+        // [pattern body...] or [pattern if guard body...]
+        let (pattern, guard, body) = match arm {
+            Expression::List(arm) => match &arm[..] {
+                [pattern, Expression::Symbol(kw), guard, body @ ..] if kw == "if" => {
+                    (pattern, Some(guard), body)
+                }
+                [pattern, body @ ..] => (pattern, None, body),
+                [] => return Err(DracaError::syntax("`match` arms need a pattern")),
+            },
+            _ => {
+                return Err(DracaError::syntax(
+                    "`match` arms must look like `[pattern body...]`",
+                ));
+            }
+        };
+
+        let Some(mut arm_env) = bind_pattern(pattern, &value, env)? else {
+            continue;
+        };
+
+        if let Some(guard) = guard
+            && !eval_condition(guard, &mut arm_env)?
+        {
+            continue;
+        }
+
+        return eval_body(body, arm_env);
+    }
+
+    Err(DracaError::NoMatch(value.to_string()))
+}
+
+fn eval_set(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    match list {
+        [Expression::Symbol(name), value] => {
+            check_bindable(name, "set!")?;
+            let value = eval_expr(value.clone(), env)?;
+            env.set(name, value).map_err(|e| e.at(name.span()))?;
+            Ok(Expression::Nil)
+        }
+        [_, _] => Err(DracaError::syntax("`set!` needs a symbol to assign to")),
+        _ => Err(DracaError::arity("set!", ArgCount::Exactly(2), list.len())),
+    }
+}
+
+fn eval_lambda(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    match list {
+        [params, body @ ..] => {
+            let params = match params {
+                Expression::List(p) => Params::parse(p)?,
+                _ => return Err(DracaError::syntax("`lambda` parameters must be a list")),
+            };
+
+            Ok(Expression::Function(Rc::new(Procedure {
+                name: None,
+                params,
+                body: body.to_vec(),
+                env: env.clone(),
+                is_loop: false,
+            })))
+        }
+        _ => Err(DracaError::arity("lambda", ArgCount::AtLeast(1), 0)),
+    }
+}
+
+/// Define `name` as a procedure that calls `builtin` with `leading` followed by its own `params`.
+///
+/// This is how `defstruct!` and `defenum!` close over the type they define, since builtins
+/// cannot.
+fn define_wrapper(
+    env: &mut Environment,
+    name: &str,
+    builtin: &str,
+    leading: Vec<Expression>,
+    params: Vec<Symbol>,
+) {
+    let mut call = vec![Expression::symbol(builtin)];
+    call.extend(leading);
+    call.extend(params.iter().cloned().map(Expression::Symbol));
+
+    let proc = Procedure {
+        name: Some(name.into()),
+        params: Params::required(params),
+        body: vec![Expression::list(call)],
+        env: env.clone(),
+        is_loop: false,
+    };
+    env.insert(name, Expression::Function(Rc::new(proc)));
+}
+
+fn quoted_symbol(symbol: &Symbol) -> Expression {
+    Expression::list([
+        Expression::symbol("quote"),
+        Expression::Symbol(symbol.clone()),
+    ])
+}
+
+/// The items of a declaration list, written either as `[a b]` or as `(list a b)`.
+fn declared_items(expr: &Expression) -> Option<&[Expression]> {
+    match expr {
+        Expression::List(list) => match list.first() {
+            Some(Expression::Symbol(head)) if head == "list" => Some(&list[1..]),
+            _ => Some(list),
+        },
+        _ => None,
+    }
+}
+
+/// `(defstruct! name [fields...])` defines `make-name`, a `name->field` accessor for every field,
+/// `name?`, and `(name-with value 'field new)`, which returns a copy with one field changed.
+fn eval_defstruct(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let [Expression::Symbol(name), Expression::List(fields)] = list else {
+        return Err(DracaError::syntax(
+            "`defstruct!` takes a name and a list of fields",
+        ));
+    };
+
+    let fields = fields
+        .iter()
+        .map(|field| match field {
+            Expression::Symbol(field) => Ok(field.clone()),
+            _ => Err(DracaError::syntax("struct fields must be symbols")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let proto = Expression::Struct(Rc::new(Struct {
+        ty: Rc::new(StructType {
+            name: name.as_str().into(),
+            fields: fields.iter().map(|field| field.as_str().into()).collect(),
+        }),
+        values: vec![Expression::Nil; fields.len()],
+    }));
+
+    define_wrapper(
+        env,
+        &format!("make-{name}"),
+        "std::structs::make",
+        vec![proto.clone()],
+        fields.clone(),
+    );
+    define_wrapper(
+        env,
+        &format!("{name}?"),
+        "std::structs::is?",
+        vec![proto.clone()],
+        vec![Symbol::from("value")],
+    );
+    define_wrapper(
+        env,
+        &format!("{name}-with"),
+        "std::structs::with",
+        vec![proto.clone()],
+        ["self", "field", "value"].map(Symbol::from).to_vec(),
+    );
+
+    for field in &fields {
+        define_wrapper(
+            env,
+            &format!("{name}->{field}"),
+            "std::structs::get",
+            vec![proto.clone(), quoted_symbol(field)],
+            vec![Symbol::from("self")],
+        );
+    }
+
+    Ok(Expression::Symbol(name.clone()))
+}
+
+/// `(defenum! name [variants...] (:impl [('method lambda)...]))`
+///
+/// Every variant is either a bare name like `None` or a list like `(Some value)`. Variants and
+/// methods are defined in the enum's own namespace, as `name::Some` and `name::method`, and each
+/// method is also defined as `name/method`. `name?` checks whether a value is of the enum. A
+/// method whose first parameter is not of the enum raises an error.
+fn eval_defenum(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    let [Expression::Symbol(name), variants, clauses @ ..] = list else {
+        return Err(DracaError::syntax(
+            "`defenum!` takes a name and a list of variants",
+        ));
+    };
+
+    let Some(variants) = declared_items(variants) else {
+        return Err(DracaError::syntax("enum variants must be a list"));
+    };
+
+    let variants = variants
+        .iter()
+        .map(|variant| match variant {
+            Expression::Symbol(variant) => Ok((variant.clone(), vec![])),
+            Expression::List(variant) => match &variant[..] {
+                [Expression::Symbol(variant), fields @ ..] => fields
+                    .iter()
+                    .map(|field| match field {
+                        Expression::Symbol(field) => Ok(field.clone()),
+                        _ => Err(DracaError::syntax("variant fields must be symbols")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|fields| (variant.clone(), fields)),
+                _ => Err(DracaError::syntax("variants must be named by a symbol")),
+            },
+            _ => Err(DracaError::syntax(
+                "variants must look like `Name` or `(Name fields...)`",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let ty = Rc::new(EnumType {
+        name: name.as_str().into(),
+        variants: variants
+            .iter()
+            .map(|(variant, fields)| (variant.as_str().into(), fields.len()))
+            .collect(),
+    });
+    let proto = Expression::Enum(Rc::new(Enum {
+        ty: Rc::clone(&ty),
+        variant: 0,
+        values: vec![],
+    }));
+
+    for (idx, (variant, fields)) in variants.into_iter().enumerate() {
+        let full_name = format!("{name}::{variant}");
+
+        if fields.is_empty() {
+            let value = Enum {
+                ty: Rc::clone(&ty),
+                variant: idx,
+                values: vec![],
+            };
+            env.insert(&full_name, Expression::Enum(Rc::new(value)));
+        } else {
+            define_wrapper(
+                env,
+                &full_name,
+                "std::enums::make",
+                vec![proto.clone(), quoted_symbol(&variant)],
+                fields,
+            );
+        }
+    }
+
+    define_wrapper(
+        env,
+        &format!("{name}?"),
+        "std::enums::is?",
+        vec![proto.clone()],
+        vec![Symbol::from("value")],
+    );
+
+    // Methods can use the variants and each other without qualifying them.
+    let mut method_env = env.clone().with_scope(Namespace::from_str(name));
+
+    for clause in clauses {
+        let methods = match clause {
+            Expression::List(clause) => match &clause[..] {
+                [Expression::Symbol(kind), methods] if kind == ":impl" => declared_items(methods),
+                [Expression::Symbol(kind), ..] if kind != ":impl" => {
+                    return Err(DracaError::syntax(format!(
+                        "unknown `defenum!` clause `{kind}`"
+                    )));
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(methods) = methods else {
+            return Err(DracaError::syntax(
+                "`defenum!` clauses must look like `(:impl [methods...])`",
+            ));
+        };
+
+        for method in methods {
+            let Expression::List(method) = method else {
+                return Err(DracaError::syntax(
+                    "methods must look like `('name (lambda ...))`",
+                ));
+            };
+
+            let [
+                Expression::Symbol(method) | Expression::Quoted(box Expression::Symbol(method)),
+                lambda,
+            ] = &method[..]
+            else {
+                return Err(DracaError::syntax(
+                    "methods must look like `('name (lambda ...))`",
+                ));
+            };
+
+            let Expression::Function(mut proc) = eval_expr(lambda.clone(), &mut method_env)? else {
+                return Err(DracaError::syntax(format!(
+                    "method `{method}` must be a function"
+                )));
+            };
+
+            let full_name = format!("{name}::{method}");
+            let proc_mut = Rc::make_mut(&mut proc);
+            proc_mut.name = Some(full_name.as_str().into());
+
+            if let Some(self_param) = proc_mut.params.required.first().cloned() {
+                proc_mut.body.insert(
+                    0,
+                    Expression::list([
+                        Expression::symbol("std::enums::expect"),
+                        proto.clone(),
+                        Expression::Symbol(self_param),
+                    ]),
+                );
+            }
+
+            env.insert(
+                &format!("{name}/{method}"),
+                Expression::Function(Rc::clone(&proc)),
+            );
+            env.insert(&full_name, Expression::Function(proc));
+        }
+    }
+
+    Ok(Expression::Symbol(name.clone()))
+}
+
+fn eval_defmacro(list: &[Expression], env: &mut Environment) -> Result<Expression, DracaError> {
+    match list {
+        // (defmacro name (params...) body...)
+        [Expression::Symbol(name), rest @ ..] => {
+            let Expression::Function(mut proc) = eval_lambda(rest, env)? else {
+                unreachable!("`lambda` always makes a function")
+            };
+            Rc::make_mut(&mut proc).name = Some(name.as_str().into());
+
+            env.insert(name, Expression::Macro(proc));
+            Ok(Expression::Symbol(name.clone()))
+        }
+        [_, ..] => Err(DracaError::syntax("`defmacro` needs a name")),
+        [] => Err(DracaError::arity("defmacro", ArgCount::AtLeast(2), 0)),
+    }
+}
+
+/// Expand the macro call that `list` evaluates to, once or until its head is no longer a macro.
+///
+/// Anything that is not a macro call is handed back as is.
+fn eval_macroexpand(
+    list: &[Expression],
+    env: &mut Environment,
+    fully: bool,
+) -> Result<Expression, DracaError> {
+    let [form] = list else {
+        let name = if fully {
+            "macroexpand"
+        } else {
+            "macroexpand-1"
+        };
+        return Err(DracaError::arity(name, ArgCount::Exactly(1), list.len()));
+    };
+
+    let mut form = match eval_expr(form.clone(), env)? {
+        Expression::Quoted(box quoted) => quoted,
+        other => other,
+    };
+
+    while let Expression::List(call) = &form
+        && let Some(Expression::Symbol(name)) = call.first()
+        && let Some(Expression::Macro(proc)) = env.get(name)
+    {
+        form = expand_macro(proc, name, call, env)?;
+
+        if !fully {
+            break;
+        }
+    }
+
+    Ok(form)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{env::Environment, int, lisp, number::Number};
+
+    use super::*;
+
+    fn setup_env() -> Environment {
+        Environment::empty().core().build()
+    }
+
+    fn eval_err(code: &str, env: &mut Environment) -> DracaError {
+        let ast = crate::parser::parse(code).expect("Could not parse text!!!");
+        eval(ast[0].clone(), env)
+            .expect_err("Evaluation should have failed")
+            .into_kind()
+    }
+
+    #[test]
+    fn tail_call_in_if() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (count n) (if (= n 0) 'done (count (- n 1))))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(count 1000000)", &mut env),
+            Expression::Quoted(Box::new(Expression::symbol("done")))
+        );
+    }
+
+    #[test]
+    fn tail_call_in_let() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (sum n acc) (if (= n 0) acc (let ((m (- n 1))) (sum m (+ acc 1)))))",
+            &mut env
+        );
+
+        assert_eq!(lisp!("(sum 1000000 0)", &mut env), int!(1000000));
+    }
+
+    #[test]
+    fn tail_call_through_lambda() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (count n) ((lambda (m) (if (= m 0) m (count (- m 1)))) n))",
+            &mut env
+        );
+
+        assert_eq!(lisp!("(count 1000000)", &mut env), int!(0));
+    }
+
+    #[test]
+    fn integers_stay_integers() {
+        let mut env = setup_env();
+
+        assert!(matches!(
+            lisp!("(rem 7 2)", &mut env),
+            Expression::Number(Number::Int(1))
+        ));
+        assert!(matches!(
+            lisp!("(len (list 1 2 3))", &mut env),
+            Expression::Number(Number::Int(3))
+        ));
+        assert!(matches!(
+            lisp!(
+                "(let next ((i 0)) (if (< i 10) (next (+ i 1)) i))",
+                &mut env
+            ),
+            Expression::Number(Number::Int(10))
+        ));
+        assert!(matches!(
+            lisp!("(rem 7.0 2)", &mut env),
+            Expression::Number(Number::Float(1.0))
+        ));
+    }
+
+    #[test]
+    fn forward_reference() {
+        let mut env = setup_env();
+        lisp!("(define (first-plus-one) (+ (later) 1))", &mut env);
+        lisp!("(define (later) 41)", &mut env);
+
+        assert_eq!(lisp!("(first-plus-one)", &mut env), int!(42));
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (even? n) (if (= n 0) #t (odd? (- n 1))))",
+            &mut env
+        );
+        lisp!(
+            "(define (odd? n) (if (= n 0) #f (even? (- n 1))))",
+            &mut env
+        );
+
+        assert_eq!(lisp!("(even? 10)", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(odd? 7)", &mut env), Expression::Bool(true));
+    }
+
+    #[test]
+    fn redefinition_is_seen_by_closures() {
+        let mut env = setup_env();
+        lisp!("(define (helper) 1)", &mut env);
+        lisp!("(define (caller) (helper))", &mut env);
+        lisp!("(define (helper) 2)", &mut env);
+
+        assert_eq!(lisp!("(caller)", &mut env), int!(2));
+    }
+
+    #[test]
+    fn lexical_bindings_are_captured() {
+        let mut env = setup_env();
+        lisp!("(define (adder n) (lambda (x) (+ x n)))", &mut env);
+        lisp!("(define add-two (adder 2))", &mut env);
+        lisp!("(define n 100)", &mut env);
+
+        assert_eq!(lisp!("(add-two 1)", &mut env), int!(3));
+    }
+
+    #[test]
+    fn error_kinds() {
+        let mut env = setup_env();
+
+        assert!(matches!(
+            eval_err("(undefined-thing 1)", &mut env),
+            DracaError::UndefinedSymbol(s) if s == "undefined-thing"
+        ));
+        assert!(matches!(
+            eval_err("(+ 1 \"two\")", &mut env),
+            DracaError::TypeMismatch { expected, found } if expected == "number" && found == "string"
+        ));
+        assert!(matches!(
+            eval_err("(cons 1)", &mut env),
+            DracaError::Arity { name, expected: ArgCount::Exactly(2), got: 1 } if name == "cons"
+        ));
+        assert!(matches!(
+            eval_err("(panic \"oh no\")", &mut env),
+            DracaError::UserPanic(msg) if msg == "oh no"
+        ));
+    }
+
+    #[test]
+    fn errors_point_at_their_source() {
+        let mut env = setup_env();
+        let ast = crate::parser::parse_source("test.dr", "(define (f) 1)\n(+ 1\n   (f) nope)")
+            .expect("Could not parse text!!!");
+
+        let mut result = Ok(Expression::Nil);
+        for expr in ast {
+            result = eval_expr(expr, &mut env);
+        }
+
+        let err = result.expect_err("Evaluation should have failed");
+        let location = err.span().and_then(|span| span.location()).unwrap();
+
+        assert!(matches!(err.kind(), DracaError::UndefinedSymbol(s) if s == "nope"));
+        assert_eq!(location.name, "test.dr");
+        assert_eq!((location.line, location.column, location.width), (3, 8, 4));
+    }
+
+    #[test]
+    fn backtrace_builtin() {
+        let mut env = setup_env();
+        lisp!("(define (inner) (std::sys::backtrace))", &mut env);
+        lisp!(
+            "(define/in-namespace my::ns (define (outer x) (car (list (inner)))))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(my::ns::outer 1)", &mut env),
+            lisp!(
+                "(list (list (quote inner) 0) (list (quote my::ns::outer) 1))",
+                &mut env
+            )
+        );
+    }
+
+    #[test]
+    fn macros_get_unevaluated_arguments() {
+        let mut env = setup_env();
+        lisp!(
+            "(defmacro my-unless (c then else) (list (quote if) c else then))",
+            &mut env
+        );
+
+        assert_eq!(lisp!("(my-unless (= 1 2) 10 (car))", &mut env), int!(10));
+    }
+
+    #[test]
+    fn macroexpand() {
+        let mut env = setup_env();
+        lisp!(
+            "(defmacro my-unless (c then else) (list (quote if) c else then))",
+            &mut env
+        );
+        lisp!(
+            "(defmacro my-when (c body) (list (quote my-unless) c #f body))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(macroexpand-1 '(my-when #t 1))", &mut env),
+            lisp!("(quote (my-unless #t #f 1))", &mut env)
+        );
+        assert_eq!(
+            lisp!("(macroexpand '(my-when #t 1))", &mut env),
+            lisp!("(quote (if #t 1 #f))", &mut env)
+        );
+        assert_eq!(
+            lisp!("(macroexpand '(+ 1 2))", &mut env),
+            lisp!("(quote (+ 1 2))", &mut env)
+        );
+    }
+
+    #[test]
+    fn macros_in_namespaces() {
+        let mut env = setup_env();
+        lisp!(
+            "(define/in-namespace my::macros (defmacro twice (x) (list (quote +) x x)))",
+            &mut env
+        );
+
+        assert_eq!(lisp!("(my::macros::twice 2)", &mut env), int!(4));
+        assert!(matches!(
+            eval_err("(twice 3)", &mut env),
+            DracaError::UndefinedSymbol(s) if s == "twice"
+        ));
+
+        lisp!("(require my::macros)", &mut env);
+        assert_eq!(lisp!("(twice 3)", &mut env), int!(6));
+    }
+
+    #[test]
+    fn later_namespaces_shadow_earlier_ones() {
+        let mut env = setup_env();
+        lisp!("(define/in-namespace first (define x 1))", &mut env);
+        lisp!("(define/in-namespace second (define x 2))", &mut env);
+
+        lisp!("(require first)", &mut env);
+        lisp!("(require second)", &mut env);
+        assert_eq!(lisp!("(+ x 0)", &mut env), int!(2));
+        assert_eq!(lisp!("(+ first::x 0)", &mut env), int!(1));
+
+        lisp!("(require first)", &mut env);
+        assert_eq!(lisp!("(+ x 0)", &mut env), int!(1));
+
+        lisp!("(define x 3)", &mut env);
+        assert_eq!(lisp!("(+ x 0)", &mut env), int!(3));
+    }
+
+    #[test]
+    fn gensym_avoids_capture() {
+        let mut env = setup_env();
+        lisp!(
+            "(defmacro my-or (a b)
+               (let ((tmp (gensym)))
+                 (list (list (quote lambda) (list tmp) (list (quote if) tmp tmp b)) a)))",
+            &mut env
+        );
+
+        assert_ne!(lisp!("(gensym)", &mut env), lisp!("(gensym)", &mut env));
+        assert_eq!(
+            lisp!("(let ((g #t)) (my-or #f g))", &mut env),
+            Expression::Bool(true)
+        );
+    }
+
+    #[test]
+    fn quasiquote() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!(
+                "(let ((x 1) (xs (list 2 3))) `(a ,x ,@xs b ,(+ x 3)))",
+                &mut env
+            ),
+            lisp!("(quote (a 1 2 3 b 4))", &mut env)
+        );
+        assert_eq!(
+            lisp!("(let ((x 1)) `(a `(b ,(c ,x))))", &mut env),
+            lisp!("(quote (a (quasiquote (b (unquote (c 1))))))", &mut env)
+        );
+        assert!(matches!(
+            eval_err("(list ,1)", &mut env),
+            DracaError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn quasiquote_in_macros() {
+        let mut env = setup_env();
+        lisp!("(defmacro flip (f a b) `(,f ,b ,a))", &mut env);
+        lisp!("(defmacro sum-of (xs) `(+ 0 ,@xs))", &mut env);
+
+        assert_eq!(lisp!("(flip - 1 10)", &mut env), int!(9));
+        assert_eq!(lisp!("(sum-of (1 2 3))", &mut env), int!(6));
+    }
+
+    #[test]
+    fn match_patterns() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (describe x)
+               (match x
+                 [0 'zero]
+                 [\"hi\" 'greeting]
+                 ['red 'colour]
+                 [() 'empty]
+                 [(a) (list 'one a)]
+                 [(a _ . rest) if (= a 1) (list 'starts-with-one rest)]
+                 [(a b . rest) (list a b rest)]
+                 [n if (> n 100) 'big]
+                 [_ 'other]))",
+            &mut env
+        );
+
+        let describe = |code: &str, env: &mut Environment| {
+            lisp!(&format!("(describe {code})"), env).to_string()
+        };
+
+        assert_eq!(describe("0", &mut env), "'zero");
+        assert_eq!(describe("\"hi\"", &mut env), "'greeting");
+        assert_eq!(describe("'red", &mut env), "'colour");
+        assert_eq!(describe("(list)", &mut env), "'empty");
+        assert_eq!(describe("(list 5)", &mut env), "('one 5)");
+        assert_eq!(
+            describe("(list 1 2 3 4)", &mut env),
+            "('starts-with-one (3 4))"
+        );
+        assert_eq!(describe("'(2 3)", &mut env), "(2 3 ())");
+        assert_eq!(describe("101", &mut env), "'big");
+        assert_eq!(describe("7", &mut env), "'other");
+    }
+
+    #[test]
+    fn match_without_a_matching_arm() {
+        let mut env = setup_env();
+
+        assert!(matches!(
+            eval_err("(match 3 [1 'one] [2 'two])", &mut env),
+            DracaError::NoMatch(value) if value == "3"
+        ));
+    }
+
+    #[test]
+    fn pattern_binding_forms() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(if-let (a b) (list 1 2) (+ a b) 0)", &mut env),
+            int!(3)
+        );
+        assert_eq!(
+            lisp!("(if-let (a b) (list 1) (+ a b) 0)", &mut env),
+            int!(0)
+        );
+        assert_eq!(
+            lisp!("(when-let (_ . rest) (list 1 2 3) (len rest))", &mut env),
+            int!(2)
+        );
+        assert_eq!(
+            lisp!("(when-let (x) (list) (car))", &mut env),
+            Expression::Nil
+        );
+        assert_eq!(
+            lisp!(
+                "(let-else (x y) (list 4 5) (panic \"no\") (* x y))",
+                &mut env
+            ),
+            int!(20)
+        );
+        assert!(matches!(
+            eval_err("(let-else (x y) (list 4) (panic \"no pair\") (* x y))", &mut env),
+            DracaError::UserPanic(msg) if msg == "no pair"
+        ));
+        assert!(matches!(
+            eval_err("(let-else (x y) (list 4) 0 (* x y))", &mut env),
+            DracaError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn pattern_bindings_are_scoped() {
+        let mut env = setup_env();
+        lisp!("(define x 1)", &mut env);
+
+        assert_eq!(lisp!("(if-let (x) (list 2) x 0)", &mut env), int!(2));
+        assert_eq!(lisp!("(+ x 0)", &mut env), int!(1));
+    }
+
+    #[test]
+    fn structs() {
+        let mut env = setup_env();
+        lisp!("(defstruct! point [x y])", &mut env);
+        lisp!("(define p (make-point 1 2))", &mut env);
+
+        assert_eq!(
+            lisp!("(list p)", &mut env).to_string(),
+            "(point { x: 1, y: 2 })"
+        );
+        assert_eq!(lisp!("(point->y p)", &mut env), int!(2));
+        assert_eq!(lisp!("(point? p)", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(point? 1)", &mut env), Expression::Bool(false));
+        assert_eq!(lisp!("(point->x (point-with p 'x 10))", &mut env), int!(10));
+        assert_eq!(lisp!("(point->x p)", &mut env), int!(1));
+        assert_eq!(
+            lisp!("(= p (make-point 1 2))", &mut env),
+            Expression::Bool(true)
+        );
+        assert_eq!(
+            lisp!("(= p (make-point 2 1))", &mut env),
+            Expression::Bool(false)
+        );
+        assert!(matches!(
+            eval_err("(point->x (list 1 2))", &mut env),
+            DracaError::TypeMismatch { expected, .. } if expected == "struct `point`"
+        ));
+        assert!(matches!(
+            eval_err("(point-with p 'z 1)", &mut env),
+            DracaError::Other(msg) if msg == "struct `point` has no field `z`"
+        ));
+    }
+
+    fn define_option(env: &mut Environment) {
+        lisp!(
+            "(defenum! option
+               (list (Some v) None)
+               (:impl (list
+                 ('is-some
+                   (lambda (self)
+                     (match self
+                       [(Some _) #t]
+                       [(None) #f])))
+                 ('unwrap-or
+                   (lambda (self default)
+                     (match self
+                       [(Some x) x]
+                       [None default])))
+                 ('map
+                   (lambda (self f)
+                     (match self
+                       [(Some x) (Some (f x))]
+                       [None None]))))))",
+            env
+        );
+    }
+
+    #[test]
+    fn enums() {
+        let mut env = setup_env();
+        define_option(&mut env);
+
+        assert_eq!(
+            lisp!("(list (option::Some 1) option::None)", &mut env).to_string(),
+            "(Some(1) None)"
+        );
+        assert_eq!(
+            lisp!("(option::is-some (option::Some 1))", &mut env),
+            Expression::Bool(true)
+        );
+        assert_eq!(
+            lisp!("(option::is-some option::None)", &mut env),
+            Expression::Bool(false)
+        );
+        assert_eq!(
+            lisp!("(option::unwrap-or option::None 7)", &mut env),
+            int!(7)
+        );
+        assert_eq!(
+            lisp!("(option/unwrap-or (option::Some 3) 7)", &mut env),
+            int!(3)
+        );
+        assert_eq!(
+            lisp!(
+                "(option::map (option::Some 1) (lambda (x) (+ x 1)))",
+                &mut env
+            ),
+            lisp!("(option::Some 2)", &mut env)
+        );
+        assert_eq!(
+            lisp!("(option? option::None)", &mut env),
+            Expression::Bool(true)
+        );
+        assert_eq!(lisp!("(option? (list))", &mut env), Expression::Bool(false));
+    }
+
+    #[test]
+    fn enum_namespaces_and_type_checks() {
+        let mut env = setup_env();
+        define_option(&mut env);
+        lisp!("(require option)", &mut env);
+
+        assert_eq!(
+            lisp!(
+                "(match (list (Some 1) 2) [((None) _) 0] [((Some a) b) (+ a b)])",
+                &mut env
+            ),
+            int!(3)
+        );
+        assert_eq!(lisp!("(unwrap-or (Some 5) 0)", &mut env), int!(5));
+        assert!(matches!(
+            eval_err("(is-some 5)", &mut env),
+            DracaError::TypeMismatch { expected, found } if expected == "enum `option`" && found == "int"
+        ));
+        assert!(matches!(
+            eval_err("(std::enums::make None (quote Some))", &mut env),
+            DracaError::Arity { name, expected: ArgCount::Exactly(1), got: 0 } if name == "Some"
+        ));
+    }
+
+    #[test]
+    fn redefined_types_reject_old_values() {
+        let mut env = setup_env();
+        lisp!("(defstruct! point [x y])", &mut env);
+        lisp!("(define p (make-point 1 2))", &mut env);
+        define_option(&mut env);
+        lisp!("(define o (option::Some 1))", &mut env);
+
+        lisp!("(defstruct! point [x y])", &mut env);
+        define_option(&mut env);
+
+        assert_eq!(lisp!("(point? p)", &mut env), Expression::Bool(false));
+        assert!(matches!(
+            eval_err("(point->x p)", &mut env),
+            DracaError::TypeMismatch { expected, .. } if expected == "struct `point`"
+        ));
+        assert_eq!(lisp!("(option? o)", &mut env), Expression::Bool(false));
+        assert!(matches!(
+            eval_err("(option::is-some o)", &mut env),
+            DracaError::TypeMismatch { expected, .. } if expected == "enum `option`"
+        ));
+    }
+
+    #[test]
+    fn try_returns_early() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (add-parsed a b)
+               (Ok (+ (? (std::conv::string->number a)) (? (std::conv::string->number b)))))",
+            &mut env
+        );
+        lisp!(
+            "(define (first-or-none lst) (if (empty? lst) None (Some (car lst))))",
+            &mut env
+        );
+        lisp!(
+            "(define (double-first lst) (Some (* 2 (? (first-or-none lst)))))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(add-parsed \"1\" \"2\")", &mut env),
+            Expression::ok(int!(3))
+        );
+        assert!(matches!(
+            lisp!("(add-parsed \"1\" \"two\")", &mut env),
+            Expression::Enum(en) if en.is_result() && en.variant_name() == "Err"
+        ));
+        assert_eq!(
+            lisp!("(double-first (list 4))", &mut env),
+            Expression::some(int!(8))
+        );
+        assert_eq!(lisp!("(double-first (list))", &mut env), Expression::none());
+        assert_eq!(backtrace::depth(), 0);
+        assert!(matches!(
+            eval_err("(? None)", &mut env),
+            DracaError::Syntax(_)
+        ));
+        assert!(matches!(
+            eval_err("(? 1)", &mut env),
+            DracaError::TypeMismatch { expected, found } if expected == "option or result" && found == "int"
+        ));
+    }
+
+    #[test]
+    fn try_catch_finally() {
+        let mut env = setup_env();
+        lisp!("(define cleaned 0)", &mut env);
+        lisp!("(define (fail x) (panic \"bad {0}\" x))", &mut env);
+
+        assert_eq!(lisp!("(try 1 2)", &mut env), int!(2));
+        assert_eq!(
+            lisp!("(try (fail 3) (catch e (error->message e)))", &mut env),
+            Expression::string("bad 3")
+        );
+        assert_eq!(
+            lisp!("(try (car) (catch e (error->kind e)))", &mut env),
+            Expression::symbol("arity")
+        );
+        assert_eq!(backtrace::depth(), 0);
+        assert_eq!(
+            lisp!(
+                "(try (+ 1 1) (catch e 0) (finally (define cleaned 1)))",
+                &mut env
+            ),
+            int!(2)
+        );
+        assert_eq!(lisp!("(+ cleaned 0)", &mut env), int!(1));
+
+        assert!(matches!(
+            eval_err("(try (fail 1) (finally (define cleaned 2)))", &mut env),
+            DracaError::UserPanic(msg) if msg == "bad 1"
+        ));
+        assert_eq!(lisp!("(+ cleaned 0)", &mut env), int!(2));
+    }
+
+    #[test]
+    fn try_does_not_catch_returns() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (first-some a b) (try (? a) (catch e 'caught)) b)",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(first-some None (Some 2))", &mut env),
+            Expression::none()
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (sign n) (cond ((< n 0) 'negative) [(= n 0) 'zero] (else 'positive)))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(list (sign -2) (sign 0) (sign 5))", &mut env).to_string(),
+            "('negative 'zero 'positive)"
+        );
+        assert_eq!(lisp!("(cond (#f 1))", &mut env), Expression::Nil);
+        assert_eq!(
+            lisp!("(when (= 1 1) (define w 1) (+ w 1))", &mut env),
+            int!(2)
+        );
+        assert_eq!(lisp!("(when #f (car))", &mut env), Expression::Nil);
+        assert_eq!(lisp!("(unless #f 1 2)", &mut env), int!(2));
+        assert_eq!(lisp!("(unless #t (car))", &mut env), Expression::Nil);
+    }
+
+    #[test]
+    fn and_or_short_circuit() {
+        let mut env = setup_env();
+
+        assert_eq!(lisp!("(and)", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(or)", &mut env), Expression::Bool(false));
+        assert_eq!(lisp!("(and #t (= 1 1))", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(and #f (car))", &mut env), Expression::Bool(false));
+        assert_eq!(lisp!("(or #t (car))", &mut env), Expression::Bool(true));
+        assert_eq!(lisp!("(or #f (= 1 2))", &mut env), Expression::Bool(false));
+        assert!(matches!(
+            eval_err("(and #t 1)", &mut env),
+            DracaError::TypeMismatch { expected, found } if expected == "bool" && found == "int"
+        ));
+    }
+
+    #[test]
+    fn set_is_seen_by_closures() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (make-counter)
+               (let ((mut n 0))
+                 (lambda () (set! n (+ n 1)) n)))",
+            &mut env
+        );
+        lisp!("(define counter (make-counter))", &mut env);
+        lisp!("(counter)", &mut env);
+
+        assert_eq!(lisp!("(counter)", &mut env), int!(2));
+
+        lisp!("(define mut total 0)", &mut env);
+        lisp!("(define (add! x) (set! total (+ total x)))", &mut env);
+        lisp!("(add! 5)", &mut env);
+        lisp!("(add! 6)", &mut env);
+        assert_eq!(lisp!("(+ total 0)", &mut env), int!(11));
+
+        lisp!(
+            "(define/in-namespace my::ns (define mut level 1))",
+            &mut env
+        );
+        lisp!("(set! my::ns::level 2)", &mut env);
+        assert_eq!(lisp!("(+ my::ns::level 0)", &mut env), int!(2));
+    }
+
+    #[test]
+    fn set_needs_mut() {
+        let mut env = setup_env();
+        lisp!("(define fixed 1)", &mut env);
+
+        assert!(matches!(
+            eval_err("(set! fixed 2)", &mut env),
+            DracaError::Immutable(name) if name == "fixed"
+        ));
+        assert!(matches!(
+            eval_err("(let ((x 1)) (set! x 2))", &mut env),
+            DracaError::Immutable(name) if name == "x"
+        ));
+        assert!(matches!(
+            eval_err("(set! missing 2)", &mut env),
+            DracaError::UndefinedSymbol(name) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn arity_is_checked() {
+        let mut env = setup_env();
+        lisp!("(define (pair a b) (list a b))", &mut env);
+
+        assert!(matches!(
+            eval_err("(pair 1)", &mut env),
+            DracaError::Arity { name, expected: ArgCount::Exactly(2), got: 1 } if name == "pair"
+        ));
+        assert!(matches!(
+            eval_err("(pair 1 2 3)", &mut env),
+            DracaError::Arity { name, expected: ArgCount::Exactly(2), got: 3 } if name == "pair"
+        ));
+        assert!(matches!(
+            eval_err("((lambda (x) x))", &mut env),
+            DracaError::Arity { name, got: 0, .. } if name == "<lambda>"
+        ));
+    }
+
+    #[test]
+    fn optional_and_rest_params() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (greet name &optional (greeting \"hello\") punct)
+               (list greeting name punct))",
+            &mut env
+        );
+        lisp!(
+            "(define (count-rest first &rest more) (len more))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(greet \"ann\")", &mut env),
+            lisp!("(list \"hello\" \"ann\" nil)", &mut env)
+        );
+        assert_eq!(
+            lisp!("(greet \"ann\" \"hi\" \"!\")", &mut env),
+            lisp!("(list \"hi\" \"ann\" \"!\")", &mut env)
+        );
+        assert!(matches!(
+            eval_err("(greet 1 2 3 4)", &mut env),
+            DracaError::Arity {
+                expected: ArgCount::Between(1, 3),
+                got: 4,
+                ..
+            }
+        ));
+
+        assert_eq!(lisp!("(count-rest 1)", &mut env), int!(0));
+        assert_eq!(lisp!("(count-rest 1 2 3)", &mut env), int!(2));
+        assert!(matches!(
+            eval_err("(count-rest)", &mut env),
+            DracaError::Arity {
+                expected: ArgCount::AtLeast(1),
+                got: 0,
+                ..
+            }
+        ));
+
+        // Defaults are evaluated at each call, and can see earlier parameters.
+        lisp!("(define (twice x &optional (y (* x 2))) y)", &mut env);
+        assert_eq!(lisp!("(twice 3)", &mut env), int!(6));
+        assert_eq!(lisp!("(twice 3 1)", &mut env), int!(1));
+    }
+
+    #[test]
+    fn keyword_params() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (make-point &key (x 0) (y 0)) (list x y))",
+            &mut env
+        );
+
+        assert_eq!(
+            lisp!("(make-point)", &mut env),
+            lisp!("(list 0 0)", &mut env)
+        );
+        assert_eq!(
+            lisp!("(make-point :y 2 :x 1)", &mut env),
+            lisp!("(list 1 2)", &mut env)
+        );
+        assert!(matches!(
+            eval_err("(make-point :z 1)", &mut env),
+            DracaError::Syntax(msg) if msg == "`make-point` has no keyword parameter `:z`"
+        ));
+        assert!(matches!(
+            eval_err("(make-point :x)", &mut env),
+            DracaError::Syntax(msg) if msg == "`make-point` takes keyword arguments in `:key value` pairs"
+        ));
+
+        assert!(matches!(
+            eval_err("(lambda (&rest) nil)", &mut env),
+            DracaError::Syntax(_)
+        ));
+        assert!(matches!(
+            eval_err("(lambda (&key a &optional b) nil)", &mut env),
+            DracaError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn sequential_and_recursive_let() {
+        let mut env = setup_env();
+        lisp!("(define x 10)", &mut env);
+
+        assert_eq!(lisp!("(let ((x 1) (y (+ x 1))) y)", &mut env), int!(11));
+        assert_eq!(lisp!("(let* ((x 1) (y (+ x 1))) y)", &mut env), int!(2));
+        assert_eq!(
+            lisp!(
+                "(letrec ((even? (lambda (n) (if (= n 0) #t (odd? (- n 1)))))
+                          (odd? (lambda (n) (if (= n 0) #f (even? (- n 1))))))
+                   (even? 10))",
+                &mut env
+            ),
+            Expression::Bool(true)
+        );
+        assert_eq!(lisp!("(let* ((mut n 1)) (set! n 5) n)", &mut env), int!(5));
+    }
+
+    #[test]
+    fn tail_call_in_named_let() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!(
+                "(let next ((i 0) (acc 0))
+                   (if (= i 1000000) acc (next (+ i 1) (+ acc 2))))",
+                &mut env
+            ),
+            int!(2000000)
+        );
+        assert!(matches!(
+            eval_err("(let next ((i 0)) (next))", &mut env),
+            DracaError::Arity { name, .. } if name == "next"
+        ));
+    }
+
+    #[test]
+    fn tail_call_in_letrec_body() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (go n) (if (= n 0) 0 (letrec ((x 1)) (go (- n 1)))))",
+            &mut env
+        );
+
+        assert_eq!(lisp!("(go 1000000)", &mut env), int!(0));
+        assert_eq!(
+            lisp!(
+                "(letrec ((count (lambda (i) (if (= i 1000000) i (count (+ i 1))))))
+                   (count 0))",
+                &mut env
+            ),
+            int!(1000000)
+        );
+    }
+
+    #[test]
+    fn recursive_lets_are_freed() {
+        let mut env = setup_env();
+
+        let Expression::Function(next) = lisp!(
+            "(let next ((i 0)) (if (< i 3) (next (+ i 1)) next))",
+            &mut env
+        ) else {
+            panic!("a named `let` should hand back its own procedure");
+        };
+        assert_eq!(Rc::strong_count(&next), 1);
+
+        // A `letrec` lambda that outlives the body keeps its bindings.
+        lisp!(
+            "(define escaped (letrec ((f (lambda (n) (if (= n 0) 7 (f (- n 1)))))) f))",
+            &mut env
+        );
+        assert_eq!(lisp!("(escaped 3)", &mut env), int!(7));
+        lisp!(
+            "(define escaped (letrec ((f (lambda () 7))) (lambda () (f))))",
+            &mut env
+        );
+        assert_eq!(lisp!("(escaped)", &mut env), int!(7));
+    }
+
+    #[test]
+    fn loops() {
+        let mut env = setup_env();
+
+        lisp!("(define mut total 0)", &mut env);
+        lisp!(
+            "(for x in (range 1 5) do (set! total (+ total x)))",
+            &mut env
+        );
+        assert_eq!(lisp!("(+ total 0)", &mut env), int!(10));
+
+        lisp!("(define mut i 0)", &mut env);
+        assert_eq!(
+            lisp!("(while (< i 100000) (set! i (+ i 1)))", &mut env),
+            Expression::Nil
+        );
+        assert_eq!(lisp!("(+ i 0)", &mut env), int!(100000));
+
+        assert_eq!(
+            lisp!(
+                "(loop (set! i (- i 1)) (when (= i 10) (break (* i 2))))",
+                &mut env
+            ),
+            int!(20)
+        );
+
+        lisp!("(define mut letters \"\")", &mut env);
+        lisp!(
+            "(loop for c in \"a-b-c\" do
+               (when (= c #\\-) (continue))
+               (set! letters (format \"{0}{1}\" letters c)))",
+            &mut env
+        );
+        assert_eq!(
+            lisp!("(format \"{0}\" letters)", &mut env),
+            Expression::String("abc".into())
+        );
+
+        assert_eq!(
+            lisp!(
+                "(for (k v) in '((a 1) (b 2)) (when (= v 2) (break k)))",
+                &mut env
+            ),
+            Expression::symbol("b")
+        );
+    }
+
+    #[test]
+    fn break_stays_in_its_loop() {
+        let mut env = setup_env();
+        lisp!("(define (escape) (break 1))", &mut env);
+
+        assert!(matches!(
+            eval_err("(loop (escape))", &mut env),
+            DracaError::Syntax(_)
+        ));
+        assert!(matches!(
+            eval_err("(break)", &mut env),
+            DracaError::Syntax(msg) if msg == "`break` outside of a loop"
+        ));
+        assert_eq!(
+            lisp!("(loop (try (break 5) (catch e 0)))", &mut env),
+            int!(5)
+        );
+    }
+
+    #[test]
+    fn special_forms_cannot_be_rebound() {
+        let mut env = setup_env();
+
+        for code in [
+            "(define (loop n) (if (= n 0) 0 (loop (- n 1))))",
+            "(define mut while 1)",
+            "(set! for 1)",
+            "(let loop ((i 0)) (if (= i 3) i (loop (+ i 1))))",
+        ] {
+            assert!(matches!(eval_err(code, &mut env), DracaError::Syntax(_)));
+        }
+        assert_eq!(lisp!("(loop (break 3))", &mut env), int!(3));
+    }
+
+    #[test]
+    fn break_out_of_named_let() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!("(while #t (let next ((i 0)) (break 5)))", &mut env),
+            int!(5)
+        );
+        assert_eq!(
+            lisp!(
+                "(let* ((mut seen 0))
+                   (for x in '(1 2 3)
+                     (let next ((i x))
+                       (when (= i 2) (continue))
+                       (set! seen (+ seen i))))
+                   seen)",
+                &mut env
+            ),
+            int!(4)
+        );
+        assert_eq!(backtrace::depth(), 0);
+
+        lisp!("(define (escape) (let next ((i 0)) (break i)))", &mut env);
+        assert!(matches!(
+            eval_err("(loop (escape))", &mut env),
+            DracaError::Syntax(_)
+        ));
+        // A `letrec` helper is an ordinary procedure, like one made with `define`.
+        assert!(matches!(
+            eval_err(
+                "(block 'found
+                   (letrec ((walk (lambda (i) (if (= i 3) (break 'found i) (walk (+ i 1))))))
+                     (walk 0)))",
+                &mut env
+            ),
+            DracaError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn early_return() {
+        let mut env = setup_env();
+        lisp!(
+            "(define (find-first xs pred)
+               (for x in xs (when (pred x) (return x)))
+               nil)",
+            &mut env
+        );
+        lisp!("(define (inner) (return 1) 2)", &mut env);
+        lisp!("(define (outer) (+ (inner) 10))", &mut env);
+
+        assert_eq!(
+            lisp!("(find-first '(1 2 3 4) (lambda (x) (> x 2)))", &mut env),
+            int!(3)
+        );
+        assert_eq!(
+            lisp!("(find-first '(1 2) (lambda (x) (> x 2)))", &mut env),
+            Expression::Nil
+        );
+        // `return` only leaves the function it is in.
+        assert_eq!(lisp!("(outer)", &mut env), int!(11));
+        assert_eq!(backtrace::depth(), 0);
+
+        assert!(matches!(
+            eval_err("(return 1)", &mut env),
+            DracaError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn labeled_blocks() {
+        let mut env = setup_env();
+
+        assert_eq!(lisp!("(block 'done 1 2)", &mut env), int!(2));
+        assert_eq!(
+            lisp!("(block (quote done) (break (quote done) 1) 2)", &mut env),
+            int!(1)
+        );
+        assert_eq!(
+            lisp!(
+                "(block 'outer
+                   (for x in (range 10)
+                     (block 'inner
+                       (when (= x 3) (break 'outer (* x 10)))
+                       (break 'inner)))
+                   'unreachable)",
+                &mut env
+            ),
+            int!(30)
+        );
+        assert_eq!(
+            lisp!("(block 'outer (loop (break 'outer 7)))", &mut env),
+            int!(7)
+        );
+
+        assert!(matches!(
+            eval_err("(block 'a (break 'b 1))", &mut env),
+            DracaError::Syntax(msg) if msg.contains("`'b`")
+        ));
+        lisp!("(define (escape) (break 'outer 1))", &mut env);
+        assert!(matches!(
+            eval_err("(block 'outer (escape))", &mut env),
+            DracaError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn errors_carry_a_backtrace() {
+        let mut env = setup_env();
+        lisp!("(define (boom) (car))", &mut env);
+        lisp!("(define (caller) (+ 1 (boom)))", &mut env);
+
+        let ast = crate::parser::parse("(caller)").expect("Could not parse text!!!");
+        let err = eval(ast[0].clone(), &mut env).expect_err("Evaluation should have failed");
+        let names = err
+            .backtrace()
+            .unwrap()
+            .frames()
+            .iter()
+            .map(|frame| frame.name.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["boom", "caller"]);
+        assert_eq!(backtrace::depth(), 0);
     }
 }
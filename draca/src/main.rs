@@ -4,11 +4,16 @@
 
 use std::error::Error;
 
+mod backtrace;
 mod core;
 mod env;
+mod error;
 mod eval;
+mod number;
 mod parser;
+mod pattern;
 mod repl;
+mod source;
 
 const HELP: &str = "
 draca --help
@@ -29,7 +34,16 @@ fn main() -> Result<(), Box<dyn Error>> {
             println!("{HELP}");
             Ok(())
         }
-        [file] if !file.starts_with('-') => env::run_file(file),
+        [file] if !file.starts_with('-') => {
+            if let Err(e) = env::run_file(file) {
+                eprintln!("error: {e}");
+                if let Some(backtrace) = e.backtrace() {
+                    eprintln!("{backtrace}");
+                }
+                std::process::exit(1)
+            }
+            Ok(())
+        }
         _ => {
             eprintln!("{HELP}");
             std::process::exit(1)
@@ -0,0 +1,393 @@
+//! Arbitrary-precision integers, which integers become when they no longer fit in an `i64`.
+
+use std::{cmp::Ordering, fmt::Display};
+
+/// A signed integer of any size, stored as its sign and its magnitude in base 2^32.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    /// Least significant digit first, with no trailing zeros, so zero has no digits.
+    digits: Vec<u32>,
+}
+
+const BASE: u64 = 1 << 32;
+
+fn trim(mut digits: Vec<u32>) -> Vec<u32> {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0;
+
+    for (i, &digit) in long.iter().enumerate() {
+        let total = u64::from(digit) + u64::from(short.get(i).copied().unwrap_or(0)) + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    sum.push(carry as u32);
+
+    trim(sum)
+}
+
+/// `a - b`, where `a` is at least `b`.
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut diff = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+
+    for (i, &digit) in a.iter().enumerate() {
+        let total = i64::from(digit) - i64::from(b.get(i).copied().unwrap_or(0)) - borrow;
+        diff.push(total as u32);
+        borrow = i64::from(total < 0);
+    }
+
+    trim(diff)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let total = u64::from(x) * u64::from(y) + u64::from(product[i + j]) + carry;
+            product[i + j] = total as u32;
+            carry = total >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+
+    trim(product)
+}
+
+fn divrem_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut rem = 0u64;
+
+    for (i, &digit) in a.iter().enumerate().rev() {
+        let current = (rem << 32) | u64::from(digit);
+        quotient[i] = (current / u64::from(divisor)) as u32;
+        rem = current % u64::from(divisor);
+    }
+
+    (trim(quotient), rem as u32)
+}
+
+fn shl_bits(a: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return a.to_vec();
+    }
+
+    let mut shifted = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0;
+    for &digit in a {
+        shifted.push((digit << shift) | carry);
+        carry = digit >> (32 - shift);
+    }
+    shifted.push(carry);
+
+    shifted
+}
+
+fn shr_bits(a: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return trim(a.to_vec());
+    }
+
+    let shifted = (0..a.len())
+        .map(|i| (a[i] >> shift) | a.get(i + 1).map_or(0, |next| next << (32 - shift)))
+        .collect();
+
+    trim(shifted)
+}
+
+/// Long division of magnitudes, as in Knuth's Algorithm D.
+fn divrem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_mag(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+
+    if let [divisor] = b {
+        let (quotient, rem) = divrem_small(a, *divisor);
+        return (quotient, trim(vec![rem]));
+    }
+
+    // Normalise so the divisor's top digit has its high bit set, which keeps each guessed
+    // quotient digit within two of the real one.
+    let shift = b.last().map_or(0, |top| top.leading_zeros());
+    let divisor = trim(shl_bits(b, shift));
+    let mut rem = shl_bits(a, shift);
+    if rem.len() == a.len() {
+        rem.push(0);
+    }
+
+    let n = divisor.len();
+    let m = rem.len() - n;
+    let mut quotient = vec![0u32; m];
+    let top = u64::from(divisor[n - 1]);
+    let next = u64::from(divisor[n - 2]);
+
+    for j in (0..m).rev() {
+        let current = (u64::from(rem[j + n]) << 32) | u64::from(rem[j + n - 1]);
+        let mut guess = current / top;
+        let mut guess_rem = current % top;
+
+        while guess >= BASE || guess * next > ((guess_rem << 32) | u64::from(rem[j + n - 2])) {
+            guess -= 1;
+            guess_rem += top;
+            if guess_rem >= BASE {
+                break;
+            }
+        }
+
+        // Subtract `guess * divisor` from the current window of the remainder.
+        let mut borrow = 0;
+        let mut carry = 0;
+        for i in 0..n {
+            let product = guess * u64::from(divisor[i]) + carry;
+            carry = product >> 32;
+            let total = i64::from(rem[i + j]) - borrow - (product & 0xffff_ffff) as i64;
+            rem[i + j] = total as u32;
+            borrow = i64::from(total < 0);
+        }
+        let total = i64::from(rem[j + n]) - borrow - carry as i64;
+        rem[j + n] = total as u32;
+
+        // The guess was one too big, so add the divisor back.
+        if total < 0 {
+            guess -= 1;
+            let mut carry = 0;
+            for i in 0..n {
+                let sum = u64::from(rem[i + j]) + u64::from(divisor[i]) + carry;
+                rem[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            rem[j + n] = rem[j + n].wrapping_add(carry as u32);
+        }
+
+        quotient[j] = guess as u32;
+    }
+
+    (trim(quotient), shr_bits(&rem[..n], shift))
+}
+
+impl BigInt {
+    fn new(negative: bool, digits: Vec<u32>) -> Self {
+        let digits = trim(digits);
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    pub fn one() -> Self {
+        Self::new(false, vec![1])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Parse an optionally signed string of decimal digits.
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::parse_radix(text, 10)
+    }
+
+    /// Parse an optionally signed string of digits in base `radix`, which is from 2 to 36.
+    pub fn parse_radix(text: &str, radix: u32) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        // Take as many digits at a time as a `u32` can hold.
+        let chunk_len = (1..)
+            .take_while(|&len| radix.checked_pow(len).is_some())
+            .count();
+
+        let mut magnitude = Vec::new();
+        for chunk in digits.as_bytes().chunks(chunk_len) {
+            let scale = radix.pow(chunk.len() as u32);
+            let value = chunk.iter().fold(0, |acc, &digit| {
+                acc * radix + char::from(digit).to_digit(radix).unwrap_or(0)
+            });
+            magnitude = add_mag(&mul_mag(&magnitude, &[scale]), &[value]);
+        }
+
+        Some(Self::new(negative, magnitude))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        let magnitude = match self.digits[..] {
+            [] => 0,
+            [low] => u64::from(low),
+            [low, high] => (u64::from(high) << 32) | u64::from(low),
+            _ => return None,
+        };
+
+        match (self.negative, i64::try_from(magnitude)) {
+            (false, Ok(n)) => Some(n),
+            (true, Ok(n)) => Some(-n),
+            (true, Err(_)) if magnitude == i64::MIN.unsigned_abs() => Some(i64::MIN),
+            _ => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &digit| acc * BASE as f64 + f64::from(digit));
+
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::new(!self.negative, self.digits.clone())
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(false, self.digits.clone())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return Self::new(self.negative, add_mag(&self.digits, &other.digits));
+        }
+
+        match cmp_mag(&self.digits, &other.digits) {
+            Ordering::Less => Self::new(other.negative, sub_mag(&other.digits, &self.digits)),
+            _ => Self::new(self.negative, sub_mag(&self.digits, &other.digits)),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(
+            self.negative != other.negative,
+            mul_mag(&self.digits, &other.digits),
+        )
+    }
+
+    /// The quotient rounded towards zero, and the remainder, which has the sign of `self`.
+    ///
+    /// `other` must not be zero.
+    pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+        let (quotient, rem) = divrem_mag(&self.digits, &other.digits);
+        (
+            Self::new(self.negative != other.negative, quotient),
+            Self::new(self.negative, rem),
+        )
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::one();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base);
+            }
+        }
+
+        result
+    }
+
+    /// The greatest common divisor of `self` and `other`, which is never negative.
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.abs();
+        let mut b = other.abs();
+
+        while !b.is_zero() {
+            let rem = a.div_rem(&b).1;
+            a = b;
+            b = rem;
+        }
+
+        a
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        let magnitude = n.unsigned_abs();
+        Self::new(n < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.digits, &other.digits),
+            (true, true) => cmp_mag(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off nine decimal digits at a time, least significant first.
+        let mut chunks = Vec::new();
+        let mut magnitude = self.digits.clone();
+        while !magnitude.is_empty() {
+            let (quotient, rem) = divrem_small(&magnitude, 1_000_000_000);
+            chunks.push(rem);
+            magnitude = quotient;
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{first}")?;
+        }
+        for chunk in chunks {
+            write!(f, "{chunk:09}")?;
+        }
+
+        Ok(())
+    }
+}
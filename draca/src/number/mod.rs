@@ -0,0 +1,272 @@
+//! Numbers: exact integers and fractions, and floats.
+//!
+//! Integers are `i64`s until they overflow, when they become [`BigInt`]s, and dividing integers
+//! that do not divide evenly gives a [`Rational`]. Exact results always take the simplest of
+//! these that can hold them. Anything involving a float gives a float, and every kind of number
+//! compares with every other by value, so `(= 1 1.0)`.
+
+mod bigint;
+mod rational;
+
+use std::{cmp::Ordering, fmt::Display, rc::Rc};
+
+pub use bigint::BigInt;
+pub use rational::Rational;
+
+use crate::error::DracaError;
+
+#[derive(Debug, Clone)]
+pub enum Number {
+    Int(i64),
+    /// An integer too big for an `i64`.
+    Big(Rc<BigInt>),
+    /// A fraction that is not a whole number.
+    Ratio(Rc<Rational>),
+    Float(f64),
+}
+
+use Number::{Big, Float, Int, Ratio};
+
+/// Check that `divisor` is not zero, as integer division and remainder need.
+pub fn nonzero(divisor: i64, what: &str) -> Result<i64, DracaError> {
+    if divisor == 0 {
+        Err(divide_by_zero(what))
+    } else {
+        Ok(divisor)
+    }
+}
+
+fn nonzero_big(divisor: &BigInt, what: &str) -> Result<(), DracaError> {
+    if divisor.is_zero() {
+        Err(divide_by_zero(what))
+    } else {
+        Ok(())
+    }
+}
+
+fn divide_by_zero(what: &str) -> DracaError {
+    DracaError::Arithmetic(format!("attempt to {what} with a divisor of zero"))
+}
+
+/// Check that `exponent` can be used to raise an integer to a power.
+pub fn exponent(exponent: i64) -> Result<u32, DracaError> {
+    u32::try_from(exponent).map_err(|_| power_too_large(exponent))
+}
+
+fn power_too_large(exponent: i64) -> DracaError {
+    DracaError::Arithmetic(format!(
+        "cannot raise an integer to the power of `{exponent}`"
+    ))
+}
+
+impl Number {
+    /// The simplest number equal to `n`.
+    pub fn from_big(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => Int(n),
+            None => Big(Rc::new(n)),
+        }
+    }
+
+    /// The simplest number equal to `n`.
+    pub fn from_rational(n: Rational) -> Self {
+        if n.is_integer() {
+            Self::from_big(n.numer().clone())
+        } else {
+            Ratio(Rc::new(n))
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Int(_) | Big(_) => "int",
+            Ratio(_) => "rational",
+            Float(_) => "float",
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Int(n) => *n as f64,
+            Big(n) => n.to_f64(),
+            Ratio(n) => n.to_f64(),
+            Float(n) => *n,
+        }
+    }
+
+    /// Round towards zero. Floats saturate at the bounds of an `i64`, like `as` does.
+    pub fn trunc(&self) -> Self {
+        match self {
+            Int(_) | Big(_) => self.clone(),
+            Ratio(n) => Self::from_big(n.numer().div_rem(n.denom()).0),
+            Float(n) => Int(*n as i64),
+        }
+    }
+
+    /// Like [`Self::trunc`], but saturating at the bounds of an `i64` for every kind of number.
+    pub fn as_i64(&self) -> i64 {
+        match self.trunc() {
+            Int(n) => n,
+            Big(n) if n.is_negative() => i64::MIN,
+            _ => i64::MAX,
+        }
+    }
+
+    /// This number as an exact fraction, if it is exact.
+    fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Int(n) => Some(Rational::from(BigInt::from(*n))),
+            Big(n) => Some(Rational::from(BigInt::clone(n))),
+            Ratio(n) => Some(Rational::clone(n)),
+            Float(_) => None,
+        }
+    }
+
+    /// Apply `int` if both numbers are `i64`s and it does not overflow, `float` if either is a
+    /// float, and `exact` otherwise.
+    fn arith(
+        &self,
+        other: &Self,
+        int: impl FnOnce(i64, i64) -> Option<i64>,
+        exact: impl FnOnce(&Rational, &Rational) -> Result<Rational, DracaError>,
+        float: impl FnOnce(f64, f64) -> f64,
+    ) -> Result<Self, DracaError> {
+        if let (Int(a), Int(b)) = (self, other)
+            && let Some(n) = int(*a, *b)
+        {
+            return Ok(Int(n));
+        }
+
+        match (self.to_rational(), other.to_rational()) {
+            (Some(a), Some(b)) => exact(&a, &b).map(Self::from_rational),
+            _ => Ok(Float(float(self.as_f64(), other.as_f64()))),
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(other, i64::checked_add, |a, b| Ok(a.add(b)), |a, b| a + b)
+    }
+
+    pub fn sub(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(other, i64::checked_sub, |a, b| Ok(a.sub(b)), |a, b| a - b)
+    }
+
+    pub fn mul(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(other, i64::checked_mul, |a, b| Ok(a.mul(b)), |a, b| a * b)
+    }
+
+    /// Exact division, which gives a fraction if integers do not divide evenly.
+    pub fn div(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(
+            other,
+            |a, b| match a.checked_rem(b) {
+                Some(0) => a.checked_div(b),
+                _ => None,
+            },
+            Rational::div,
+            |a, b| a / b,
+        )
+    }
+
+    pub fn rem(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(
+            other,
+            i64::checked_rem,
+            |a, b| Ok(a.div_rem(b, "calculate the remainder")?.1),
+            |a, b| a % b,
+        )
+    }
+
+    /// Division that rounds down to a whole number, so the remainder is never negative.
+    pub fn div_euclid(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(
+            other,
+            i64::checked_div_euclid,
+            |a, b| Ok(a.div_rem_euclid(b, "divide")?.0),
+            f64::div_euclid,
+        )
+    }
+
+    /// The remainder of [`Self::div_euclid`], which is never negative.
+    pub fn rem_euclid(&self, other: &Self) -> Result<Self, DracaError> {
+        self.arith(
+            other,
+            i64::checked_rem_euclid,
+            |a, b| Ok(a.div_rem_euclid(b, "calculate the remainder")?.1),
+            f64::rem_euclid,
+        )
+    }
+
+    pub fn neg(&self) -> Result<Self, DracaError> {
+        match self {
+            Float(n) => Ok(Float(-n)),
+            _ => Int(0).sub(self),
+        }
+    }
+
+    /// Exact numbers raised to integer powers stay exact, so a negative power gives a fraction.
+    pub fn pow(&self, other: &Self) -> Result<Self, DracaError> {
+        match (self, other) {
+            (Int(a), Int(b))
+                if let Ok(b) = u32::try_from(*b)
+                    && let Some(n) = a.checked_pow(b) =>
+            {
+                Ok(Int(n))
+            }
+            (_, Int(b)) if let Some(a) = self.to_rational() => a.pow(*b).map(Self::from_rational),
+            (a, b) => Ok(Float(a.as_f64().powf(b.as_f64()))),
+        }
+    }
+
+    /// The numerator of this number as a fraction in lowest terms.
+    pub fn numerator(&self) -> Option<Self> {
+        match self {
+            Ratio(n) => Some(Self::from_big(n.numer().clone())),
+            Float(_) => None,
+            _ => Some(self.clone()),
+        }
+    }
+
+    /// The denominator of this number as a fraction in lowest terms, which is positive.
+    pub fn denominator(&self) -> Option<Self> {
+        match self {
+            Ratio(n) => Some(Self::from_big(n.denom().clone())),
+            Float(_) => None,
+            _ => Some(Int(1)),
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if let (Int(a), Int(b)) = (self, other) {
+            return a.partial_cmp(b);
+        }
+
+        match (self.to_rational(), other.to_rational()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Int(n) => write!(f, "{n}"),
+            Big(n) => write!(f, "{n}"),
+            Ratio(n) => write!(f, "{n}"),
+            // Written so they read back as the same float.
+            Float(n) if n.is_nan() => write!(f, "+nan.0"),
+            Float(n) if n.is_infinite() => write!(f, "{}inf.0", if *n > 0.0 { "+" } else { "-" }),
+            // `{:?}` keeps the `.0` on whole floats, so they do not look like integers.
+            Float(n) => write!(f, "{n:?}"),
+        }
+    }
+}
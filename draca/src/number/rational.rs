@@ -0,0 +1,148 @@
+//! Exact fractions, which come out of dividing integers that do not divide evenly.
+
+use std::{cmp::Ordering, fmt::Display};
+
+use super::{bigint::BigInt, nonzero_big, power_too_large};
+use crate::error::DracaError;
+
+/// A fraction in lowest terms, with a positive denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    numer: BigInt,
+    denom: BigInt,
+}
+
+impl Rational {
+    /// `numer / denom` in lowest terms. `denom` must not be zero.
+    fn new(numer: BigInt, denom: BigInt) -> Self {
+        let gcd = numer.gcd(&denom);
+        let (mut numer, mut denom) = (numer.div_rem(&gcd).0, denom.div_rem(&gcd).0);
+
+        if denom.is_negative() {
+            numer = numer.neg();
+            denom = denom.neg();
+        }
+
+        Self { numer, denom }
+    }
+
+    pub fn numer(&self) -> &BigInt {
+        &self.numer
+    }
+
+    pub fn denom(&self) -> &BigInt {
+        &self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == BigInt::one()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numer.is_negative()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numer.to_f64() / self.denom.to_f64()
+    }
+
+    pub fn neg(&self) -> Self {
+        Self {
+            numer: self.numer.neg(),
+            denom: self.denom.clone(),
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        Self::new(
+            self.numer
+                .mul(&other.denom)
+                .add(&other.numer.mul(&self.denom)),
+            self.denom.mul(&other.denom),
+        )
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(self.numer.mul(&other.numer), self.denom.mul(&other.denom))
+    }
+
+    pub fn div(&self, other: &Self) -> Result<Self, DracaError> {
+        nonzero_big(&other.numer, "divide")?;
+        Ok(Self::new(
+            self.numer.mul(&other.denom),
+            self.denom.mul(&other.numer),
+        ))
+    }
+
+    /// The quotient rounded towards zero, and what is left over.
+    pub fn div_rem(&self, other: &Self, what: &str) -> Result<(Self, Self), DracaError> {
+        nonzero_big(&other.numer, what)?;
+        let exact = self.div(other)?;
+        let quotient = Self::from(exact.numer.div_rem(&exact.denom).0);
+        let rem = self.sub(&other.mul(&quotient));
+        Ok((quotient, rem))
+    }
+
+    /// The quotient rounded so that what is left over is never negative, and what is left over.
+    pub fn div_rem_euclid(&self, other: &Self, what: &str) -> Result<(Self, Self), DracaError> {
+        let (quotient, rem) = self.div_rem(other, what)?;
+
+        if !rem.is_negative() {
+            Ok((quotient, rem))
+        } else if other.is_negative() {
+            Ok((quotient.add(&Self::from(BigInt::one())), rem.sub(other)))
+        } else {
+            Ok((quotient.sub(&Self::from(BigInt::one())), rem.add(other)))
+        }
+    }
+
+    /// Raise to an integer power, which gives the reciprocal for negative powers.
+    pub fn pow(&self, exponent: i64) -> Result<Self, DracaError> {
+        let power =
+            u32::try_from(exponent.unsigned_abs()).map_err(|_| power_too_large(exponent))?;
+        let raised = Self::new(self.numer.pow(power), self.denom.pow(power));
+
+        if exponent < 0 {
+            Self::from(BigInt::one()).div(&raised)
+        } else {
+            Ok(raised)
+        }
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(numer: BigInt) -> Self {
+        Self {
+            numer,
+            denom: BigInt::one(),
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numer
+            .mul(&other.denom)
+            .cmp(&other.numer.mul(&self.denom))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
@@ -1,25 +1,42 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    thread::LocalKey,
+};
 
 use pest_consume::{Parser, match_nodes};
 
-use crate::env::Environment;
+use crate::{
+    env::Environment,
+    error::{ArgCount, DracaError},
+    number::{BigInt, Number},
+    source::{self, FileId, Span},
+};
 
 #[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Expression {
     Bool(bool),
-    Number(f64),
-    Symbol(String),
+    Number(Number),
+    Symbol(Symbol),
     String(String),
-    List(Vec<Expression>),
-    Func(fn(&[Expression]) -> std::result::Result<Expression, String>),
-    Function(Procedure),
+    Char(char),
+    List(List),
+    Func(fn(&[Expression]) -> std::result::Result<Expression, DracaError>),
+    Function(Rc<Procedure>),
+    /// A procedure that is given its arguments unevaluated and returns a form to evaluate instead.
+    Macro(Rc<Procedure>),
+    /// An instance of a struct defined with `defstruct!`.
+    Struct(Rc<Struct>),
+    /// A variant of an enum defined with `defenum!`.
+    Enum(Rc<Enum>),
     Nil,
     Quoted(Box<Expression>),
 }
 
 impl Expression {
-    pub fn symbol<T: Into<String>>(symbol: T) -> Self {
+    pub fn symbol<T: Into<Symbol>>(symbol: T) -> Self {
         Self::Symbol(symbol.into())
     }
 
@@ -34,6 +51,50 @@ impl Expression {
         Self::List(iter.into_iter().collect())
     }
 
+    pub fn some(value: Expression) -> Self {
+        Enum::builtin(&OPTION, 0, vec![value])
+    }
+
+    pub fn none() -> Self {
+        Enum::builtin(&OPTION, 1, vec![])
+    }
+
+    pub fn ok(value: Expression) -> Self {
+        Enum::builtin(&RESULT, 0, vec![value])
+    }
+
+    pub fn err(value: Expression) -> Self {
+        Enum::builtin(&RESULT, 1, vec![value])
+    }
+
+    /// An `error` struct, as bound by `catch`.
+    pub fn error(kind: &str, message: String) -> Self {
+        Self::Struct(Rc::new(Struct {
+            ty: ERROR.with(Rc::clone),
+            values: vec![Self::symbol(kind), Self::String(message)],
+        }))
+    }
+
+    /// The name of this value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::Number(n) => n.type_name(),
+            Self::Symbol(_) => "symbol",
+            Self::String(_) => "string",
+            Self::Char(_) => "char",
+            Self::List(_) => "list",
+            Self::Func(_) | Self::Function(_) => "function",
+            Self::Macro(_) => "macro",
+            Self::Struct(_) => "struct",
+            Self::Enum(en) if en.is_option() => "option",
+            Self::Enum(en) if en.is_result() => "result",
+            Self::Enum(_) => "enum",
+            Self::Nil => "nil",
+            Self::Quoted(_) => "quoted",
+        }
+    }
+
     pub fn fmt_string(&self) -> String {
         match self {
             Self::Bool(b) => {
@@ -45,18 +106,31 @@ impl Expression {
             }
             Self::Number(n) => n.to_string(),
             Self::String(s) => s.clone(),
+            Self::Char(c) => c.to_string(),
             Self::Nil => String::from("nil"),
-            Self::Quoted(fmt) => format!("'{}", fmt.fmt_string()),
+            Self::Quoted(fmt) => format!("'{}", fmt.fmt_nested()),
             Self::List(lst) => format!(
                 "({})",
                 lst.iter()
-                    .map(Self::fmt_string)
+                    .map(Self::fmt_nested)
                     .collect::<Vec<_>>()
                     .join(" ")
             ),
             Self::Function(_) => String::from("<function>"),
+            Self::Macro(_) => String::from("<macro>"),
+            Self::Struct(st) => st.fmt_with(Self::fmt_nested),
+            Self::Enum(en) => en.fmt_with(Self::fmt_nested),
             Self::Func(_) => String::from("<fn>"),
-            Self::Symbol(s) => s.clone(),
+            Self::Symbol(s) => s.to_string(),
+        }
+    }
+
+    /// Like [`Self::fmt_string`], for a value inside a list or other data, where a bare character
+    /// could not be told apart from a symbol or whitespace.
+    fn fmt_nested(&self) -> String {
+        match self {
+            Self::Char(_) => self.to_string(),
+            other => other.fmt_string(),
         }
     }
 }
@@ -68,21 +142,28 @@ impl Display for Expression {
             Self::Number(n) => write!(f, "{n}"),
             Self::Symbol(s) => write!(f, "{s}"),
             Self::Nil => write!(f, "nil"),
-            Self::String(s) => write!(f, "\"{s}\""),
+            // `{:?}` writes the string with its escapes, so it reads back the same.
+            Self::String(s) => write!(f, "{s:?}"),
+            Self::Char(c) => match CHAR_NAMES.iter().find(|(_, named)| named == c) {
+                Some((name, _)) => write!(f, "#\\{name}"),
+                None if c.is_control() => write!(f, "#\\u{{{:X}}}", u32::from(*c)),
+                None => write!(f, "#\\{c}"),
+            },
             Self::List(list) => {
                 let formatted_list: Vec<_> = list.iter().map(ToString::to_string).collect();
                 write!(f, "({})", formatted_list.join(" "))
             }
             Self::Func(func) => write!(f, "<{:p}>", *func as *const ()),
-            Self::Function(func) => {
+            Self::Function(func) | Self::Macro(func) => {
+                let kind = if matches!(self, Self::Macro(_)) {
+                    "macro"
+                } else {
+                    "fn"
+                };
                 write!(
                     f,
-                    "<fn>({}): {}",
-                    func.params
-                        .iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<_>>()
-                        .join(", "),
+                    "<{kind}>({}): {}",
+                    func.params,
                     func.body
                         .iter()
                         .map(ToString::to_string)
@@ -90,20 +171,566 @@ impl Display for Expression {
                         .join("\n")
                 )
             }
+            Self::Struct(st) => write!(f, "{}", st.fmt_with(ToString::to_string)),
+            Self::Enum(en) => write!(f, "{}", en.fmt_with(ToString::to_string)),
             Self::Quoted(qt) => write!(f, "'{}", qt),
         }
     }
 }
 
+/// Characters that are written by name, as in `#\space`, because they would be hard to see.
+const CHAR_NAMES: [(&str, char); 7] = [
+    ("space", ' '),
+    ("newline", '\n'),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+    ("escape", '\x1b'),
+    ("delete", '\x7f'),
+];
+
+/// A symbol, remembering where it was parsed from.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    name: Rc<str>,
+    span: Option<Span>,
+}
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.name
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Self {
+            name: value.into(),
+            span: None,
+        }
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Self {
+            name: value.into(),
+            span: None,
+        }
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(value: &String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+// Where a symbol came from has no bearing on what it is.
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.name == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.name == *other
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.name.partial_cmp(&other.name)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// A list of expressions, remembering where it was parsed from.
+#[derive(Debug, Clone, Default)]
+pub struct List {
+    items: Vec<Expression>,
+    span: Option<Span>,
+}
+
+impl List {
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Take on `span` if this list does not already know where it came from.
+    pub fn or_span(mut self, span: Option<Span>) -> Self {
+        self.span = self.span.or(span);
+        self
+    }
+}
+
+impl Deref for List {
+    type Target = Vec<Expression>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl DerefMut for List {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+impl From<Vec<Expression>> for List {
+    fn from(items: Vec<Expression>) -> Self {
+        Self { items, span: None }
+    }
+}
+
+impl FromIterator<Expression> for List {
+    fn from_iter<T: IntoIterator<Item = Expression>>(iter: T) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl IntoIterator for List {
+    type Item = Expression;
+    type IntoIter = std::vec::IntoIter<Expression>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a Expression;
+    type IntoIter = std::slice::Iter<'a, Expression>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl PartialOrd for List {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.items.partial_cmp(&other.items)
+    }
+}
+
+/// The parameter list of a [`Procedure`]:
+///
+/// ```text
+/// (required... &optional (name default)... &rest name &key (name default)...)
+/// ```
+///
+/// Every section is optional. A parameter without a default defaults to `nil`.
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct Params {
+    pub required: Vec<Symbol>,
+    pub optional: Vec<(Symbol, Expression)>,
+    pub rest: Option<Symbol>,
+    /// Passed as `:name value`.
+    pub key: Vec<(Symbol, Expression)>,
+}
+
+impl Params {
+    /// Parameters that are all required.
+    pub fn required(required: Vec<Symbol>) -> Self {
+        Self {
+            required,
+            ..Self::default()
+        }
+    }
+
+    pub fn parse(params: &[Expression]) -> std::result::Result<Self, DracaError> {
+        #[derive(PartialEq, PartialOrd)]
+        enum Section {
+            Required,
+            Optional,
+            Rest,
+            Key,
+        }
+
+        let mut parsed = Self::default();
+        let mut section = Section::Required;
+
+        for param in params {
+            let next_section = match param {
+                Expression::Symbol(s) if s == "&optional" => Some(Section::Optional),
+                Expression::Symbol(s) if s == "&rest" => Some(Section::Rest),
+                Expression::Symbol(s) if s == "&key" => Some(Section::Key),
+                _ => None,
+            };
+
+            if let Some(next_section) = next_section {
+                if next_section <= section {
+                    return Err(DracaError::syntax(format!(
+                        "`{param}` must come after the parameters it follows, and only once"
+                    )));
+                }
+                section = next_section;
+                continue;
+            }
+
+            let (name, default) = match param {
+                Expression::Symbol(name) => (name.clone(), Expression::Nil),
+                Expression::List(pair) if matches!(section, Section::Optional | Section::Key) => {
+                    match &pair[..] {
+                        [Expression::Symbol(name), default] => (name.clone(), default.clone()),
+                        _ => {
+                            return Err(DracaError::syntax(
+                                "parameters with defaults must look like `(name default)`",
+                            ));
+                        }
+                    }
+                }
+                _ => return Err(DracaError::syntax("parameter names must be symbols")),
+            };
+
+            match section {
+                Section::Required => parsed.required.push(name),
+                Section::Optional => parsed.optional.push((name, default)),
+                Section::Rest if parsed.rest.is_none() => parsed.rest = Some(name),
+                Section::Rest => {
+                    return Err(DracaError::syntax("`&rest` takes a single parameter"));
+                }
+                Section::Key => parsed.key.push((name, default)),
+            }
+        }
+
+        if section == Section::Rest && parsed.rest.is_none() {
+            return Err(DracaError::syntax("`&rest` needs a parameter name"));
+        }
+
+        Ok(parsed)
+    }
+
+    /// How many positional arguments these parameters accept.
+    pub fn arg_count(&self) -> ArgCount {
+        let required = self.required.len();
+
+        if self.rest.is_some() || !self.key.is_empty() {
+            ArgCount::AtLeast(required)
+        } else if self.optional.is_empty() {
+            ArgCount::Exactly(required)
+        } else {
+            ArgCount::Between(required, required + self.optional.len())
+        }
+    }
+}
+
+impl Display for Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let with_default = |(name, default): &(Symbol, Expression)| match default {
+            Expression::Nil => name.to_string(),
+            default => format!("({name} {default})"),
+        };
+
+        let mut params = self
+            .required
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        if !self.optional.is_empty() {
+            params.push("&optional".into());
+            params.extend(self.optional.iter().map(with_default));
+        }
+
+        if let Some(rest) = &self.rest {
+            params.push(format!("&rest {rest}"));
+        }
+
+        if !self.key.is_empty() {
+            params.push("&key".into());
+            params.extend(self.key.iter().map(with_default));
+        }
+
+        write!(f, "{}", params.join(", "))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Procedure {
-    pub params: Vec<Expression>,
+    /// The name this procedure was defined under, if any.
+    pub name: Option<Rc<str>>,
+    pub params: Params,
     pub body: Vec<Expression>,
     pub env: Environment,
+    /// Whether this is the procedure behind a named `let`, which `break` and `continue` pass
+    /// through to the loop around it.
+    pub is_loop: bool,
+}
+
+/// The name and fields of a struct defined with `defstruct!`.
+#[derive(Debug, PartialEq, PartialOrd)]
+pub struct StructType {
+    pub name: Rc<str>,
+    pub fields: Vec<Rc<str>>,
+}
+
+/// An instance of a [`StructType`], holding one value per field.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Struct {
+    pub ty: Rc<StructType>,
+    pub values: Vec<Expression>,
+}
+
+impl Struct {
+    /// Whether this is an `error`, as made by [`Expression::error`].
+    pub fn is_error(&self) -> bool {
+        ERROR.with(|error| Rc::ptr_eq(&self.ty, error))
+    }
+
+    /// Render as `name { field: value, ... }`, with each value rendered by `fmt_value`.
+    fn fmt_with(&self, fmt_value: impl Fn(&Expression) -> String) -> String {
+        if self.values.is_empty() {
+            return format!("{} {{}}", self.ty.name);
+        }
+
+        let fields = self
+            .ty
+            .fields
+            .iter()
+            .zip(&self.values)
+            .map(|(field, value)| format!("{field}: {}", fmt_value(value)))
+            .collect::<Vec<_>>();
+
+        format!("{} {{ {} }}", self.ty.name, fields.join(", "))
+    }
+}
+
+/// The name and variants of an enum defined with `defenum!`.
+#[derive(Debug, PartialEq, PartialOrd)]
+pub struct EnumType {
+    pub name: Rc<str>,
+    /// Each variant's name and how many values it holds.
+    pub variants: Vec<(Rc<str>, usize)>,
+}
+
+impl EnumType {
+    /// Find the variant called `name`, which may be qualified by the enum's name, as in
+    /// `option::Some`.
+    pub fn variant(&self, name: &str) -> Option<usize> {
+        let (path, target) = name.rsplit_once("::").unwrap_or(("", name));
+        let enum_name = self.name.rsplit("::").next().unwrap_or(&self.name);
+
+        if !path.is_empty() && path.rsplit("::").next() != Some(enum_name) {
+            return None;
+        }
+
+        self.variants
+            .iter()
+            .position(|(variant, _)| &**variant == target)
+    }
+}
+
+thread_local! {
+    static OPTION: Rc<EnumType> = Rc::new(EnumType {
+        name: "std::option".into(),
+        variants: vec![("Some".into(), 1), ("None".into(), 0)],
+    });
+    static RESULT: Rc<EnumType> = Rc::new(EnumType {
+        name: "std::result".into(),
+        variants: vec![("Ok".into(), 1), ("Err".into(), 1)],
+    });
+    static ERROR: Rc<StructType> = Rc::new(StructType {
+        name: "error".into(),
+        fields: vec!["kind".into(), "message".into()],
+    });
+}
+
+/// One variant of an [`EnumType`], with the values it holds.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Enum {
+    pub ty: Rc<EnumType>,
+    pub variant: usize,
+    pub values: Vec<Expression>,
+}
+
+impl Enum {
+    fn builtin(
+        ty: &'static LocalKey<Rc<EnumType>>,
+        variant: usize,
+        values: Vec<Expression>,
+    ) -> Expression {
+        Expression::Enum(Rc::new(Self {
+            ty: ty.with(Rc::clone),
+            variant,
+            values,
+        }))
+    }
+
+    pub fn variant_name(&self) -> &str {
+        &self.ty.variants[self.variant].0
+    }
+
+    /// Whether this is a `std::option`, as made by [`Expression::some`] and [`Expression::none`].
+    pub fn is_option(&self) -> bool {
+        OPTION.with(|option| Rc::ptr_eq(&self.ty, option))
+    }
+
+    /// Whether this is a `std::result`, as made by [`Expression::ok`] and [`Expression::err`].
+    pub fn is_result(&self) -> bool {
+        RESULT.with(|result| Rc::ptr_eq(&self.ty, result))
+    }
+
+    /// Render as `Variant(value, ...)`, or just `Variant` if it holds nothing, with each value
+    /// rendered by `fmt_value`.
+    fn fmt_with(&self, fmt_value: impl Fn(&Expression) -> String) -> String {
+        if self.values.is_empty() {
+            return self.variant_name().to_string();
+        }
+
+        let values = self.values.iter().map(fmt_value).collect::<Vec<_>>();
+
+        format!("{}({})", self.variant_name(), values.join(", "))
+    }
 }
 
 type Result<T> = std::result::Result<T, pest_consume::Error<Rule>>;
-type Node<'i> = pest_consume::Node<'i, Rule, ()>;
+/// Like [`Result`], with the error boxed so it is cheap to pass around outside the grammar.
+pub type ParseResult<T> = std::result::Result<T, Box<pest_consume::Error<Rule>>>;
+type Node<'i> = pest_consume::Node<'i, Rule, FileId>;
+
+fn span_of(input: &Node) -> Span {
+    let span = input.as_span();
+
+    Span {
+        file: *input.user_data(),
+        start: span.start(),
+        end: span.end(),
+    }
+}
+
+/// An error pointing at the text from `start` to `end` in `input`, rather than all of it.
+fn error_within(
+    input: &Node,
+    start: usize,
+    end: usize,
+    message: String,
+) -> Box<pest_consume::Error<Rule>> {
+    let span = input.as_span();
+    let within = pest::Span::new(span.get_input(), span.start() + start, span.start() + end);
+
+    Box::new(pest_consume::Error::new_from_span(
+        pest::error::ErrorVariant::CustomError { message },
+        within.unwrap_or(span),
+    ))
+}
+
+/// Decode the escapes in the text of a string literal. A backslash at the end of a line skips the
+/// line break and the indentation of the next line, so long strings can be wrapped.
+fn unescape(input: &Node) -> ParseResult<String> {
+    let text = input.as_str();
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(backslash) = rest.find('\\') {
+        unescaped.push_str(&rest[..backslash]);
+
+        let start = text.len() - rest.len() + backslash;
+        let escape = &rest[backslash + 1..];
+        // The grammar only lets a backslash through with something after it.
+        let kind = escape.chars().next().unwrap_or_default();
+        let mut len = kind.len_utf8();
+
+        match kind {
+            'n' => unescaped.push('\n'),
+            't' => unescaped.push('\t'),
+            'r' => unescaped.push('\r'),
+            '0' => unescaped.push('\0'),
+            '\\' | '"' | '\'' => unescaped.push(kind),
+            '\n' | '\r' => len = escape.len() - escape.trim_start().len(),
+            'u' => {
+                let hex = escape[1..]
+                    .strip_prefix('{')
+                    .and_then(|braced| braced.split_once('}'))
+                    .map(|(hex, _)| hex)
+                    .filter(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                    .ok_or_else(|| {
+                        error_within(
+                            input,
+                            start,
+                            start + 2,
+                            "a unicode escape must look like `\\u{1F600}`".into(),
+                        )
+                    })?;
+                len += hex.len() + 2;
+
+                let c = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        error_within(
+                            input,
+                            start,
+                            start + 1 + len,
+                            format!("`{hex}` is not a unicode code point"),
+                        )
+                    })?;
+                unescaped.push(c);
+            }
+            other => {
+                return Err(error_within(
+                    input,
+                    start,
+                    start + 1 + len,
+                    format!("unknown escape `\\{other}`"),
+                ));
+            }
+        }
+
+        rest = &escape[len..];
+    }
+
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+/// Spell a reader shorthand like `` `x `` out as the form it stands for, `(quasiquote x)`.
+fn shorthand(input: &Node, name: &str, form: Expression) -> Expression {
+    let span = span_of(input);
+
+    Expression::List(List {
+        items: vec![
+            Expression::Symbol(Symbol {
+                name: name.into(),
+                span: Some(span),
+            }),
+            form,
+        ],
+        span: Some(span),
+    })
+}
 
 #[derive(Parser)]
 #[grammar = "src/grammar.pest"]
@@ -115,22 +742,83 @@ impl Parse {
         Ok(())
     }
 
-    fn number(input: Node) -> Result<f64> {
-        input.as_str().parse().map_err(|e| input.error(e))
+    fn number(input: Node) -> Result<Number> {
+        let text = input.as_str().replace('_', "");
+        let (sign, unsigned) = match text.strip_prefix(['+', '-']) {
+            Some(unsigned) => (&text[..1], unsigned),
+            None => ("", text.as_str()),
+        };
+
+        let (radix, digits) = match unsigned.get(..2) {
+            Some("0x") => (16, &unsigned[2..]),
+            Some("0o") => (8, &unsigned[2..]),
+            Some("0b") => (2, &unsigned[2..]),
+            _ => (10, unsigned),
+        };
+
+        match unsigned {
+            "inf.0" if sign == "-" => Ok(Number::Float(f64::NEG_INFINITY)),
+            "inf.0" => Ok(Number::Float(f64::INFINITY)),
+            "nan.0" => Ok(Number::Float(f64::NAN)),
+            _ if let Some((numer, denom)) = unsigned.split_once('/') => {
+                let part = |digits: &str| BigInt::parse_radix(digits, 10).map(Number::from_big);
+                let (Some(numer), Some(denom)) = (part(&format!("{sign}{numer}")), part(denom))
+                else {
+                    return Err(input.error(format!("`{text}` is not a fraction")));
+                };
+
+                numer.div(&denom).map_err(|e| input.error(e))
+            }
+            _ if radix == 10 && unsigned.contains(['.', 'e', 'E']) => {
+                text.parse().map(Number::Float).map_err(|e| input.error(e))
+            }
+            _ => BigInt::parse_radix(&format!("{sign}{digits}"), radix)
+                .map(Number::from_big)
+                .ok_or_else(|| input.error(format!("`{text}` is not an integer"))),
+        }
+    }
+
+    fn strinner(input: Node) -> Result<String> {
+        unescape(&input).map_err(|e| *e)
     }
 
-    fn strinner(input: Node<'_>) -> Result<&str> {
+    fn rawinner(input: Node<'_>) -> Result<&str> {
         Ok(input.as_str())
     }
 
     fn string(input: Node) -> Result<String> {
         Ok(match_nodes!(input.into_children();
-            [strinner(st)] => st.to_string(),
+            [strinner(st)] => st,
+            [rawinner(st)] => st.to_string(),
         ))
     }
 
-    fn symbol(input: Node) -> Result<String> {
-        Ok(input.as_str().to_string())
+    fn char(input: Node) -> Result<char> {
+        let text = &input.as_str()[2..];
+
+        if let Some(hex) = text.strip_prefix("u{").and_then(|t| t.strip_suffix('}')) {
+            return u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| input.error(format!("`{hex}` is not a unicode code point")));
+        }
+
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => CHAR_NAMES
+                .iter()
+                .find(|(name, _)| *name == text)
+                .map(|(_, c)| *c)
+                .ok_or_else(|| input.error(format!("`#\\{text}` is not a character"))),
+        }
+    }
+
+    fn symbol(input: Node) -> Result<Symbol> {
+        Ok(Symbol {
+            name: input.as_str().into(),
+            span: Some(span_of(&input)),
+        })
     }
 
     fn nil(_input: Node) -> Result<Expression> {
@@ -138,10 +826,7 @@ impl Parse {
     }
 
     fn bool(input: Node) -> Result<Expression> {
-        Ok(Expression::Bool(match input.as_str() {
-            "#t" => true,
-            "#f" | _ => false,
-        }))
+        Ok(Expression::Bool(input.as_str() == "#t"))
     }
 
     fn quoted(input: Node) -> Result<Expression> {
@@ -150,35 +835,150 @@ impl Parse {
         ))
     }
 
+    fn quasiquoted(input: Node) -> Result<Expression> {
+        let form = match_nodes!(input.children();
+            [form(fm)] => fm,
+        );
+        Ok(shorthand(&input, "quasiquote", form))
+    }
+
+    fn unquoted(input: Node) -> Result<Expression> {
+        let form = match_nodes!(input.children();
+            [form(fm)] => fm,
+        );
+        Ok(shorthand(&input, "unquote", form))
+    }
+
+    fn spliced(input: Node) -> Result<Expression> {
+        let form = match_nodes!(input.children();
+            [form(fm)] => fm,
+        );
+        Ok(shorthand(&input, "unquote-splicing", form))
+    }
+
     fn list(input: Node) -> Result<Expression> {
-        Ok(match_nodes!(input.into_children();
-            [form(fms)..] => Expression::List(fms.collect()),
-        ))
+        let span = span_of(&input);
+        let items = input
+            .into_children()
+            .map(Self::form)
+            .collect::<Result<_>>()?;
+
+        Ok(Expression::List(List {
+            items,
+            span: Some(span),
+        }))
     }
 
     fn form(input: Node) -> Result<Expression> {
         Ok(match_nodes!(input.into_children();
             [number(n)] => Expression::Number(n),
             [quoted(q)] => q,
+            [quasiquoted(q)] => q,
+            [unquoted(u)] => u,
+            [spliced(s)] => s,
             [nil(n)] => n,
             [bool(b)] => b,
             [string(s)] => Expression::String(s),
+            [char(c)] => Expression::Char(c),
             [symbol(s)] => Expression::Symbol(s),
             [list(l)] => l,
         ))
     }
 
     fn program(input: Node) -> Result<Vec<Expression>> {
-        Ok(match_nodes!(input.into_children();
-            [list(lst).., _] => lst.collect(),
-        ))
+        input
+            .into_children()
+            .filter(|node| node.as_rule() == Rule::list)
+            .map(Self::list)
+            .collect()
     }
 }
 
-pub fn parse(input: &str) -> Result<Vec<Expression>> {
-    let inputs = Parse::parse(Rule::program, input)?;
+pub fn parse(input: &str) -> ParseResult<Vec<Expression>> {
+    parse_source("<input>", input)
+}
+
+/// Parse `input`, remembering it as `name` so errors can point back into it.
+pub fn parse_source(name: &str, input: &str) -> ParseResult<Vec<Expression>> {
+    let file = source::add(name, input);
+
+    Parse::parse_with_userdata(Rule::program, input, file)
+        .and_then(pest_consume::Nodes::single)
+        .and_then(Parse::program)
+        .map_err(|e| Box::new(e.with_path(name)))
+}
+
+#[cfg(test)]
+mod test {
+    use pest::error::LineColLocation;
+
+    use crate::{env::Environment, int, lisp};
+
+    use super::*;
 
-    let input = inputs.single()?;
+    fn setup_env() -> Environment {
+        Environment::empty().core().stdlib().build()
+    }
+
+    #[test]
+    fn escapes() {
+        let mut env = setup_env();
 
-    Parse::program(input)
+        assert_eq!(
+            lisp!(r#"(quote "a\nb\t\"c\"\\\0")"#, &mut env),
+            Expression::string("a\nb\t\"c\"\\\0")
+        );
+        assert_eq!(
+            lisp!(r#"(quote "\u{1F600} \u{e9}")"#, &mut env),
+            Expression::string("😀 é")
+        );
+        assert_eq!(
+            lisp!("(quote \"one \\\n       two\")", &mut env),
+            Expression::string("one two")
+        );
+        assert_eq!(
+            lisp!("(quote \"one\n  two\")", &mut env),
+            Expression::string("one\n  two")
+        );
+
+        let escaped = Expression::string("a\n\"b\"\\");
+        assert_eq!(escaped.to_string(), r#""a\n\"b\"\\""#);
+        assert_eq!(lisp!(&format!("(quote {escaped})"), &mut env), escaped);
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        let span = |code: &str| match parse(code) {
+            Err(e) => e.line_col,
+            Ok(_) => panic!("`{code}` should not have parsed"),
+        };
+
+        assert_eq!(span(r#"(f "a\qb")"#), LineColLocation::Span((1, 6), (1, 8)));
+        assert_eq!(
+            span(r#"(f "\u{110000}")"#),
+            LineColLocation::Span((1, 5), (1, 15))
+        );
+        assert_eq!(span(r#"(f "\u41")"#), LineColLocation::Span((1, 5), (1, 7)));
+    }
+
+    #[test]
+    fn raw_strings() {
+        let mut env = setup_env();
+
+        assert_eq!(
+            lisp!(r#"(quote r"C:\path\n")"#, &mut env),
+            Expression::string(r"C:\path\n")
+        );
+        assert_eq!(
+            lisp!(r###"(quote r#"say "hi""#)"###, &mut env),
+            Expression::string(r#"say "hi""#)
+        );
+        assert_eq!(
+            lisp!(r###"(quote r##"a "# b"##)"###, &mut env),
+            Expression::string(r##"a "# b"##)
+        );
+
+        lisp!("(define r 1)", &mut env);
+        assert_eq!(lisp!("(+ r 1)", &mut env), int!(2));
+    }
 }
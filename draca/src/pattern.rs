@@ -0,0 +1,129 @@
+//! Structural patterns, as used by `match`.
+//!
+//! - `_` matches anything.
+//! - A symbol matches anything and binds it to that name.
+//! - Numbers, strings, booleans and `nil` match themselves.
+//! - `'x` matches a value equal to `x`, such as the symbol `x`.
+//! - `(a b c)` matches a list of three values, each against its own pattern, and `(a b . rest)`
+//!   binds everything after the first two values to `rest`.
+//! - When matching a value of an enum, `None` and `(Some x)` match its variants by name instead,
+//!   since the enum says which names are variants.
+
+use crate::{
+    error::DracaError,
+    parser::{Expression, Symbol},
+};
+
+/// The names a pattern bound, in the order they appear in it.
+pub type Bindings = Vec<(Symbol, Expression)>;
+
+/// Check whether `value` matches `pattern`, pushing whatever it binds onto `bindings`.
+///
+/// If the match fails, `bindings` may hold some of the names bound before it did.
+pub fn matches(
+    pattern: &Expression,
+    value: &Expression,
+    bindings: &mut Bindings,
+) -> Result<bool, DracaError> {
+    match pattern {
+        Expression::Symbol(s) if s == "_" => Ok(true),
+        Expression::Symbol(s) if let Some(variant) = variant_of(value, s) => Ok(
+            matches!(value, Expression::Enum(en) if en.variant == variant && en.values.is_empty()),
+        ),
+        Expression::Symbol(s) => {
+            bindings.push((s.clone(), value.clone()));
+            Ok(true)
+        }
+        Expression::Bool(_)
+        | Expression::Number(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Struct(_)
+        | Expression::Enum(_)
+        | Expression::Nil => Ok(pattern == value),
+        Expression::Quoted(quoted) => Ok(match value {
+            Expression::Quoted(value) => quoted == value,
+            value => **quoted == *value,
+        }),
+        Expression::List(patterns)
+            if let Expression::Enum(en) = value
+                && let [Expression::Symbol(head), fields @ ..] = &patterns[..]
+                && let Some(variant) = variant_of(value, head) =>
+        {
+            if en.variant != variant || en.values.len() != fields.len() {
+                return Ok(false);
+            }
+
+            for (pattern, value) in fields.iter().zip(&en.values) {
+                if !matches(pattern, value, bindings)? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+        Expression::List(patterns) => {
+            let Some(values) = list_items(value) else {
+                return Ok(false);
+            };
+
+            let (fixed, rest) = split_rest(patterns)?;
+
+            if values.len() < fixed.len() || (rest.is_none() && values.len() != fixed.len()) {
+                return Ok(false);
+            }
+
+            for (pattern, value) in fixed.iter().zip(values) {
+                if !matches(pattern, value, bindings)? {
+                    return Ok(false);
+                }
+            }
+
+            match rest {
+                Some(rest) => matches(
+                    rest,
+                    &Expression::list(values[fixed.len()..].to_vec()),
+                    bindings,
+                ),
+                None => Ok(true),
+            }
+        }
+        Expression::Func(_) | Expression::Function(_) | Expression::Macro(_) => {
+            Err(DracaError::syntax("functions cannot be used as patterns"))
+        }
+    }
+}
+
+/// If `value` belongs to an enum with a variant called `name`, which one that is.
+fn variant_of(value: &Expression, name: &str) -> Option<usize> {
+    match value {
+        Expression::Enum(en) => en.ty.variant(name),
+        _ => None,
+    }
+}
+
+fn list_items(value: &Expression) -> Option<&[Expression]> {
+    match value {
+        Expression::List(list) => Some(list),
+        Expression::Quoted(quoted) => match &**quoted {
+            Expression::List(list) => Some(list),
+            Expression::Nil => Some(&[]),
+            _ => None,
+        },
+        Expression::Nil => Some(&[]),
+        _ => None,
+    }
+}
+
+/// Split `(a b . rest)` into `(a b)` and `rest`.
+fn split_rest(patterns: &[Expression]) -> Result<(&[Expression], Option<&Expression>), DracaError> {
+    let is_dot = |pattern: &Expression| matches!(pattern, Expression::Symbol(s) if s == ".");
+
+    match patterns {
+        [fixed @ .., dot, rest] if is_dot(dot) => Ok((fixed, Some(rest))),
+        _ if patterns.iter().any(is_dot) => Err(DracaError::syntax(
+            "`.` in a pattern must be followed by exactly one pattern",
+        )),
+        _ => Ok((patterns, None)),
+    }
+}
@@ -10,7 +10,11 @@ use rustyline::{
     validate::{ValidationContext, ValidationResult, Validator},
 };
 
-use crate::{env::Environment, eval::eval, parser::parse};
+use crate::{
+    env::Environment,
+    eval::eval,
+    parser::{parse, parse_source},
+};
 
 // TODO: Make this less spaghetti.
 
@@ -157,13 +161,44 @@ fn cmd_sets(env: &Environment) -> HashSet<Command> {
         "namespace/symbol",
         "namespace/as-list",
         "quote",
+        "quasiquote",
         "eval-file",
         "require",
         "deconst-fn",
         "if",
+        "cond",
+        "when",
+        "unless",
+        "and",
+        "or",
         "lambda",
         "list",
         "let",
+        "let*",
+        "letrec",
+        "set!",
+        "mut",
+        "while",
+        "loop",
+        "for",
+        "in",
+        "do",
+        "break",
+        "continue",
+        "block",
+        "return",
+        "match",
+        "try",
+        "catch",
+        "finally",
+        "if-let",
+        "when-let",
+        "let-else",
+        "defmacro",
+        "defstruct!",
+        "defenum!",
+        "macroexpand",
+        "macroexpand-1",
     ] {
         set.insert(Command::new(it, ""));
         set.insert(Command::new("", it));
@@ -171,10 +206,10 @@ fn cmd_sets(env: &Environment) -> HashSet<Command> {
 
     // Then do user set things.
     for (qualified_path, it) in env.full_path_and_name() {
-        set.insert(Command::new(&qualified_path, it));
-        set.insert(Command::new(it, &qualified_path));
+        set.insert(Command::new(&qualified_path, &it));
+        set.insert(Command::new(&it, &qualified_path));
 
-        set.insert(Command::new(it, ""));
+        set.insert(Command::new(&it, ""));
         set.insert(Command::new(&qualified_path, ""));
     }
 
@@ -204,7 +239,7 @@ pub fn repl() -> rustyline::Result<()> {
         let readline = rl.readline(&prompt);
         match readline {
             Ok(line) => {
-                let parsed_list = match parse(line.trim()) {
+                let parsed_list = match parse_source("<repl>", &line) {
                     Ok(val) => val,
                     Err(e) => {
                         eprintln!("{e}");
@@ -215,7 +250,12 @@ pub fn repl() -> rustyline::Result<()> {
                 for expr in parsed_list {
                     match eval(expr, &mut env) {
                         Ok(val) => println!("{}", Style::new().dimmed().paint(val.to_string())),
-                        Err(e) => eprintln!("==> Error: {e}"),
+                        Err(e) => {
+                            eprintln!("==> Error: {e}");
+                            if let Some(backtrace) = e.backtrace() {
+                                eprintln!("{backtrace}");
+                            }
+                        }
                     }
                 }
                 rl.add_history_entry(&line)?;
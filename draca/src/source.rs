@@ -0,0 +1,174 @@
+//! Keeps the text of everything that has been parsed, so a [`Span`] can be turned back into a file
+//! name, a line and a column when reporting errors.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    rc::Rc,
+};
+
+/// Identifies one piece of parsed source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+/// A byte range in a piece of parsed source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+struct Source {
+    name: String,
+    text: Rc<str>,
+}
+
+/// How many sources with the same name are kept. Every line typed at the REPL is a new source
+/// called `<repl>`, so without a limit a long session would keep all of them.
+const KEPT_PER_NAME: usize = 64;
+
+#[derive(Default)]
+struct Sources {
+    next: usize,
+    files: HashMap<usize, Source>,
+    /// The ids of the sources with each name, oldest first.
+    by_name: HashMap<String, VecDeque<usize>>,
+}
+
+thread_local! {
+    static SOURCES: RefCell<Sources> = RefCell::new(Sources::default());
+}
+
+/// Remember `text` under `name` and hand back an id for spans into it.
+///
+/// Adding the same text under the same name again reuses the old id. Once there are more than
+/// [`KEPT_PER_NAME`] sources with a name, the oldest is forgotten, and spans into it no longer
+/// have a [`Location`].
+pub fn add(name: impl Into<String>, text: &str) -> FileId {
+    SOURCES.with_borrow_mut(|sources| {
+        let Sources {
+            next,
+            files,
+            by_name,
+        } = sources;
+        let name = name.into();
+        let ids = by_name.entry(name.clone()).or_default();
+
+        if let Some(&id) = ids.iter().find(|id| &*files[id].text == text) {
+            return FileId(id);
+        }
+
+        let id = *next;
+        *next += 1;
+        files.insert(
+            id,
+            Source {
+                name,
+                text: text.into(),
+            },
+        );
+        ids.push_back(id);
+
+        if ids.len() > KEPT_PER_NAME
+            && let Some(oldest) = ids.pop_front()
+        {
+            files.remove(&oldest);
+        }
+
+        FileId(id)
+    })
+}
+
+/// Where a [`Span`] starts, in human terms.
+pub struct Location {
+    pub name: String,
+    /// The full text of the line the span starts on.
+    pub line_text: String,
+    /// 1-indexed.
+    pub line: usize,
+    /// 1-indexed, in characters.
+    pub column: usize,
+    /// Whitespace lining up with everything before the span on its line, keeping tabs as tabs.
+    padding: String,
+    /// How many characters of the span are on its first line.
+    pub width: usize,
+}
+
+impl Span {
+    pub fn location(&self) -> Option<Location> {
+        SOURCES.with_borrow(|sources| {
+            let source = sources.files.get(&self.file.0)?;
+            let text = &source.text;
+            let start = self.start.min(text.len());
+
+            let line_start = text[..start].rfind('\n').map_or(0, |idx| idx + 1);
+            let line_end = text[start..]
+                .find('\n')
+                .map_or(text.len(), |idx| start + idx);
+            let end = self.end.clamp(start, line_end);
+
+            Some(Location {
+                name: source.name.clone(),
+                line_text: text[line_start..line_end].to_string(),
+                line: text[..start].matches('\n').count() + 1,
+                column: text[line_start..start].chars().count() + 1,
+                padding: text[line_start..start]
+                    .chars()
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect(),
+                width: text[start..end].chars().count().max(1),
+            })
+        })
+    }
+}
+
+impl Display for Location {
+    /// Render like rustc does:
+    ///
+    /// ```text
+    ///  --> file.dr:12:5
+    ///    |
+    /// 12 |     (foo bar)
+    ///    |      ^^^
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+
+        writeln!(f, "{gutter}--> {}:{}:{}", self.name, self.line, self.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.line_text)?;
+        write!(f, "{gutter} | {}{}", self.padding, "^".repeat(self.width))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn span(file: FileId) -> Span {
+        Span {
+            file,
+            start: 0,
+            end: 1,
+        }
+    }
+
+    #[test]
+    fn old_repl_lines_are_forgotten() {
+        let first = add("<repl>", "(first)");
+        assert_eq!(add("<repl>", "(first)"), first);
+
+        let lines: Vec<_> = (0..KEPT_PER_NAME)
+            .map(|n| add("<repl>", &format!("(line {n})")))
+            .collect();
+
+        assert!(span(first).location().is_none());
+        assert!(lines.iter().all(|&line| span(line).location().is_some()));
+        assert_eq!(
+            SOURCES.with_borrow(|sources| sources.files.len()),
+            KEPT_PER_NAME
+        );
+    }
+}
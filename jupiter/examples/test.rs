@@ -46,6 +46,6 @@ fn main() {
     }
 
     for item in namespace.all_items() {
-        println!("{:#?}", item.name().expect("Checked above"));
+        println!("{:#?}", item.name());
     }
 }
@@ -220,6 +220,8 @@ where
     /// This will not automatically map a `T` to the end of `I`, such that:
     ///
     /// ```no_run
+    /// # let mut ns = jupiter::Namespace::<&str, fn()>::new("::");
+    /// # fn hello() {}
     /// ns.insert_at_module(["std", "fns"], hello); // ❌
     /// ```
     ///
@@ -334,10 +336,7 @@ impl<'a, S, T> NamespaceNode<'a, S, T> {
                 Root::Entry(entr) => entr,
                 Root::Root => return None,
             },
-            value: match &self.value {
-                Some(value) => value,
-                None => return None,
-            },
+            value: self.value.as_ref()?,
         })
     }
 
@@ -398,10 +397,7 @@ where
         let mut cur = self;
 
         for part in iter {
-            match cur.children.get(&part) {
-                Some(next) => cur = next,
-                None => return None,
-            }
+            cur = cur.children.get(&part)?;
         }
 
         Some(cur)